@@ -1,7 +1,6 @@
 #version 400
 
-out vec4 gl_FragColor;
-in vec2 gl_FragCoord;
+out vec4 f_color;
 uniform vec2 u_resolution;           // viewport resolution (in pixels)
 uniform float u_time;           // shader playback time (in seconds)
 
@@ -131,5 +130,5 @@ void main()
     // vec3 col = vec3(c);
 
     // Done.
-    gl_FragColor = vec4(clamp(col, 0., 1.), 1.);
+    f_color = vec4(clamp(col, 0., 1.), 1.);
 }
@@ -0,0 +1,145 @@
+//! Windowless runs for scripting and parameter sweeps.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use cycle::{CycleDetector, Outcome};
+use stats::{GenerationStats, Tracker};
+use world::World;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum StopReason {
+    /// The requested number of generations was reached.
+    Finished,
+    /// The world died out, stopped changing or entered a cycle.
+    Settled(Outcome),
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StopReason::Finished => f.write_str("finished"),
+            StopReason::Settled(outcome) => write!(f, "{}", outcome),
+        }
+    }
+}
+
+/// What to do once a world is found to have settled.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum OnSettle {
+    /// Report it and keep stepping.
+    Continue,
+    Stop,
+    /// Start over from a new random soup.
+    Reseed,
+}
+
+impl fmt::Display for OnSettle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            OnSettle::Continue => "continue",
+            OnSettle::Stop => "stop",
+            OnSettle::Reseed => "reseed",
+        })
+    }
+}
+
+impl FromStr for OnSettle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<OnSettle, String> {
+        match s {
+            "continue" => Ok(OnSettle::Continue),
+            "stop" => Ok(OnSettle::Stop),
+            "reseed" => Ok(OnSettle::Reseed),
+            _ => Err(format!("unknown settle action: {}, expected continue, stop or reseed", s)),
+        }
+    }
+}
+
+pub struct BatchConfig {
+    /// Generations to advance; a world whose steps span several
+    /// generations may overshoot.
+    pub generations: u64,
+    pub on_settle: OnSettle,
+    /// Generations remembered to find cycles, the longest period found.
+    pub max_period: usize,
+    /// Measure every generation, see `BatchResult::stats`.
+    pub record_stats: bool,
+}
+
+pub struct BatchResult {
+    pub reason: StopReason,
+    /// Every time the world settled, reseeded runs included.
+    pub settled: Vec<Outcome>,
+    pub first_generation: u64,
+    /// Population after every step from `first_generation` on, the initial
+    /// one included.
+    pub population: Vec<usize>,
+    /// Generations advanced per step.
+    pub step_size: u64,
+    /// Measurements of every generation in `population`, if requested.
+    pub stats: Vec<GenerationStats>,
+}
+
+/// Steps `world` according to `config`. `reseed` replaces the cells of a
+/// settled world when `config.on_settle` is `Reseed`.
+pub fn run<W, F>(world: &mut W, config: &BatchConfig, mut reseed: F) -> BatchResult
+    where W: World + ?Sized, F: FnMut(&mut W) {
+    let first_generation = world.generation();
+    let mut population = vec![world.population()];
+    let mut tracker = Tracker::new();
+    let mut stats = Vec::new();
+    if config.record_stats {
+        stats.push(tracker.record(world));
+    }
+    let mut detector = CycleDetector::new(config.max_period);
+    detector.observe(world);
+    let mut reason = StopReason::Finished;
+    let mut settled = Vec::new();
+    let target = first_generation + config.generations;
+    let mut step_size = 1;
+    while world.generation() < target {
+        let before = world.generation();
+        world.step();
+        step_size = world.generation() - before;
+        if let Some(outcome) = detector.observe(world) {
+            settled.push(outcome);
+            match config.on_settle {
+                OnSettle::Continue => {},
+                OnSettle::Stop => reason = StopReason::Settled(outcome),
+                OnSettle::Reseed => {
+                    reseed(world);
+                    detector.reset();
+                    detector.observe(world);
+                },
+            }
+        }
+        population.push(world.population());
+        if config.record_stats {
+            stats.push(tracker.record(world));
+        }
+        if reason != StopReason::Finished {
+            break;
+        }
+    }
+    BatchResult {
+        reason: reason,
+        settled: settled,
+        first_generation: first_generation,
+        population: population,
+        step_size: step_size,
+        stats: stats,
+    }
+}
+
+impl BatchResult {
+    /// Writes `generation population` lines.
+    pub fn write_population<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (i, p) in self.population.iter().enumerate() {
+            try!(writeln!(writer, "{} {}", self.first_generation + i as u64 * self.step_size, p));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+//! What lies beyond the edges of a bounded world.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Boundary {
+    /// The axis wraps around (torus).
+    Periodic,
+    /// Cells beyond the edge are always dead.
+    Dead,
+    /// Cells beyond the edge are always alive.
+    Alive,
+    /// The edge reflects: the cell beyond it mirrors the edge cell.
+    Mirror,
+}
+
+impl Boundary {
+    /// Maps coordinate `c`, which may lie one step outside `0..size`, onto
+    /// the axis. `None` means the cell is a wall, see `wall_alive`.
+    #[inline]
+    pub fn resolve(self, c: isize, size: usize) -> Option<usize> {
+        let s = size as isize;
+        if 0 <= c && c < s {
+            return Some(c as usize);
+        }
+        match self {
+            Boundary::Periodic => Some(((c % s + s) % s) as usize),
+            Boundary::Mirror => Some(if c < 0 { -c - 1 } else { 2 * s - c - 1 } as usize),
+            Boundary::Dead | Boundary::Alive => None,
+        }
+    }
+
+    /// State of wall cells produced by this boundary.
+    #[inline]
+    pub fn wall_alive(self) -> bool {
+        self == Boundary::Alive
+    }
+}
+
+impl Default for Boundary {
+    fn default() -> Boundary {
+        Boundary::Periodic
+    }
+}
+
+impl fmt::Display for Boundary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Boundary::Periodic => "periodic",
+            Boundary::Dead => "dead",
+            Boundary::Alive => "alive",
+            Boundary::Mirror => "mirror",
+        })
+    }
+}
+
+impl FromStr for Boundary {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Boundary, String> {
+        match &*s.to_lowercase() {
+            "periodic" | "torus" | "wrap" => Ok(Boundary::Periodic),
+            "dead" => Ok(Boundary::Dead),
+            "alive" => Ok(Boundary::Alive),
+            "mirror" | "reflect" => Ok(Boundary::Mirror),
+            _ => Err(format!("unknown boundary '{}', expected periodic, dead, alive or mirror", s)),
+        }
+    }
+}
+
+/// Boundaries of the x, y and z axes.
+pub type Boundaries = [Boundary; 3];
@@ -70,10 +70,10 @@ impl PerspectiveCamera {
     //     self
     // }
 
-    // pub fn with_view_dimensions(mut self, width: u32, height: u32) -> PerspectiveCamera {
-    //     self.projection.set_aspect(width as f32 / height as f32);
-    //     self
-    // }
+    pub fn with_view_dimensions(mut self, width: u32, height: u32) -> PerspectiveCamera {
+        self.projection.set_aspect(width as f32 / height as f32);
+        self
+    }
 
     // pub fn with_znear(mut self, val: f32) -> PerspectiveCamera {
     //     self.projection.set_znear(val);
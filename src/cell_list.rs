@@ -0,0 +1,78 @@
+//! Plain-text cell lists: one live cell per line as `x y z`, `#` starts a
+//! comment.
+
+use std::io::{self, BufRead, Write};
+
+use state::{State, U3d};
+use world::{World, I3d};
+
+fn invalid_data(line: usize, msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, msg))
+}
+
+/// Reads a cell list, returning the coordinates in file order.
+pub fn read<R: BufRead>(reader: R) -> io::Result<Vec<U3d>> {
+    read_lines(reader, "expected three non-negative integers", |c| c.parse::<usize>().ok())
+}
+
+/// Reads a cell list of any world; coordinates may be negative.
+pub fn read_world<R: BufRead>(reader: R) -> io::Result<Vec<I3d>> {
+    read_lines(reader, "expected three integers", |c| c.parse::<i64>().ok())
+}
+
+fn read_lines<R, T, F>(reader: R, expected: &str, parse: F) -> io::Result<Vec<(T, T, T)>>
+    where R: BufRead, T: Copy, F: Fn(&str) -> Option<T>
+{
+    let mut cells = Vec::new();
+    for (n, line) in reader.lines().enumerate() {
+        let line = try!(line);
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let coords = line.split_whitespace().map(&parse).collect::<Option<Vec<_>>>();
+        match coords {
+            Some(ref c) if c.len() == 3 => cells.push((c[0], c[1], c[2])),
+            _ => return Err(invalid_data(n + 1, expected)),
+        }
+    }
+    Ok(cells)
+}
+
+/// Writes every live cell of `state`.
+pub fn write<W: Write>(state: &State, mut writer: W) -> io::Result<()> {
+    let (xs, ys, zs) = state.dim();
+    try!(writeln!(writer, "# {}x{}x{}", xs, ys, zs));
+    if let Some(seed) = state.seed() {
+        try!(writeln!(writer, "# seed {}", seed));
+    }
+    write_world(state, writer)
+}
+
+/// Writes every live cell of any world; coordinates may be negative.
+pub fn write_world<V: World + ?Sized, W: Write>(world: &V, mut writer: W) -> io::Result<()> {
+    try!(writeln!(writer, "# {} generation {}", world.rule(), world.generation()));
+    let mut cells: Vec<I3d> = world.live_cells().collect();
+    cells.sort();
+    for (x, y, z) in cells {
+        try!(writeln!(writer, "{} {} {}", x, y, z));
+    }
+    Ok(())
+}
+
+/// Sets the listed cells alive. Fails without touching `state` if a cell
+/// lies outside the world.
+pub fn load<R: BufRead>(state: &mut State, reader: R) -> io::Result<usize> {
+    let cells = try!(read(reader));
+    let (xs, ys, zs) = state.dim();
+    if let Some(&(x, y, z)) = cells.iter().find(|&&(x, y, z)| x >= xs || y >= ys || z >= zs) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("cell ({}, {}, {}) lies outside the {}x{}x{} world", x, y, z, xs, ys, zs)
+        ));
+    }
+    for &p in cells.iter() {
+        state.set(p, true);
+    }
+    Ok(cells.len())
+}
@@ -0,0 +1,340 @@
+extern crate getopts;
+extern crate std;
+
+use game_of_life::{Rule, Region, Boundary, Boundaries, U3d};
+use game_of_life::batch::OnSettle;
+use game_of_life::hashlife;
+use game_of_life::stats::Format;
+use game_of_life::patterns::{self, PATTERNS};
+use game_of_life::server::Address;
+use game_of_life::state::neighbourhood_size;
+use game_of_life::world::check_unbounded;
+
+/// Simulation backend.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Engine {
+    /// Bounded packed world, `State`.
+    Dense,
+    /// Unbounded chunked world, `SparseWorld`.
+    Sparse,
+    /// Unbounded memoised octree, `HashLife`.
+    HashLife,
+}
+
+pub struct Options {
+    pub dim: U3d,
+    pub rule: Rule,
+    pub engine: Engine,
+    /// HashLife advances 2^hash_step generations per step.
+    pub hash_step: u8,
+    pub boundaries: Boundaries,
+    pub seed: Option<u64>,
+    pub density: f64,
+    pub region: Region,
+    /// Leave every n-th plane of the soup empty.
+    pub lattice_skip: Option<usize>,
+    /// Milliseconds between generations.
+    pub step_interval: u64,
+    /// Worker threads per step, 0 for one per CPU.
+    pub threads: usize,
+    pub window_size: (u32, u32),
+    /// Directory searched first for shaders and meshes.
+    pub asset_dir: Option<String>,
+    /// Generations to step before the first frame is shown.
+    pub start_generation: u64,
+    pub pattern: Option<String>,
+    /// Palette indices of a `.vox` pattern that start alive, all if `None`.
+    pub vox_colours: Option<Vec<u8>>,
+    /// Library patterns to place, with their corner, centred if `None`.
+    pub stamps: Vec<(&'static patterns::Pattern, Option<U3d>)>,
+    /// Where the window listens for control requests.
+    pub control: Option<Address>,
+    /// Console commands to run, from stdin if `-`.
+    pub script: Option<String>,
+    /// Run this many generations without a window.
+    pub batch: Option<u64>,
+    /// What to do once the world dies out, stops changing or cycles.
+    pub on_settle: OnSettle,
+    /// Longest period looked for.
+    pub max_period: usize,
+    /// Generations the window keeps to step back through.
+    pub history: usize,
+    /// Where F5 saves the world and F9 restores it from.
+    pub snapshot_path: String,
+    /// Where a batch run writes its final cell list.
+    pub final_state: Option<String>,
+    /// Merge coplanar faces of exported meshes into larger ones.
+    pub merge_faces: bool,
+    /// Where a batch run writes its population series.
+    pub population_file: Option<String>,
+    /// Where every generation's statistics are written.
+    pub stats_file: Option<String>,
+    /// Format of `stats_file`, guessed from its name if not given.
+    pub stats_format: Option<Format>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            dim: (50, 50, 50),
+            rule: Rule::default(),
+            engine: Engine::Dense,
+            hash_step: 0,
+            boundaries: [Boundary::Periodic; 3],
+            seed: None,
+            density: 0.5,
+            region: Region::Box(0.75),
+            lattice_skip: Some(5),
+            step_interval: 500,
+            threads: 1,
+            window_size: (1024, 768),
+            asset_dir: None,
+            start_generation: 0,
+            pattern: None,
+            vox_colours: None,
+            stamps: Vec::new(),
+            control: None,
+            script: None,
+            batch: if cfg!(feature = "headless") { Some(1000) } else { None },
+            on_settle: OnSettle::Continue,
+            max_period: 256,
+            history: 1000,
+            snapshot_path: String::from("world.snap"),
+            final_state: None,
+            merge_faces: false,
+            population_file: None,
+            stats_file: None,
+            stats_format: None,
+        }
+    }
+}
+
+/// Outcome of parsing the command line.
+pub enum Command {
+    Run(Options),
+    Help(String),
+}
+
+fn opts() -> getopts::Options {
+    let mut opts = getopts::Options::new();
+    opts.optopt("s", "size", "world size, a single number for a cube (default 50x50x50)", "XxYxZ");
+    opts.optopt("r", "rule", "rule: B5/S45, 4555 or 3D4,5/5; B3/S23 on a XxYx1 slab (default B5/S45)", "RULE");
+    opts.optopt("e", "engine", "dense, or sparse or hashlife for an unbounded world in batch runs (default dense)", "ENGINE");
+    opts.optopt("", "hash-step", "hashlife advances 2^K generations per step (default 0)", "K");
+    opts.optopt("", "boundary", "periodic, dead, alive or mirror; one for all axes or one per axis (default periodic)", "B[,B,B]");
+    opts.optopt("", "seed", "seed of the random soup", "N");
+    opts.optopt("d", "density", "fraction of live cells in the soup (default 0.5)", "P");
+    opts.optopt("", "region", "where the soup goes: whole, box[:F] or sphere[:F] (default box:0.75)", "REGION");
+    opts.optopt("", "skip", "leave every N-th plane of the soup empty, 0 for none (default 5)", "N");
+    opts.optopt("i", "interval", "milliseconds between generations (default 500)", "MS");
+    opts.optopt("t", "threads", "worker threads per step, 0 for one per CPU (default 1)", "N");
+    opts.optopt("w", "window", "window size (default 1024x768)", "WxH");
+    opts.optopt("", "assets", "directory searched first for shaders/ (GLSL 4.00, 3.30 and 1.40 variants) and src/support/cube.obj; built-in copies are used if none is found", "DIR");
+    opts.optopt("g", "start-gen", "generations to step before showing the world", "N");
+    opts.optopt("p", "pattern", "cell list, Golly 3D pattern (.rle), MagicaVoxel model (.vox) or snapshot (.snap) to load instead of a random soup", "FILE");
+    opts.optopt("", "vox-colours", "palette indices of a .vox pattern that start alive (default all)", "LIST");
+    opts.optmulti("", "stamp", "place a library pattern, centred or with its corner at X,Y,Z; the world starts empty unless --pattern is given", "NAME[@X,Y,Z]");
+    opts.optflag("", "list-patterns", "list the pattern library");
+    opts.optopt("b", "batch", "run N generations without opening a window", "N");
+    opts.optopt("", "control", "serve JSON-lines control requests on a Unix socket or a localhost port", "unix:PATH|tcp:PORT");
+    opts.optopt("", "script", "run console commands from FILE, or stdin if -; in a batch run they come before the N generations", "FILE");
+    opts.optopt("", "on-settle", "once the world is extinct, static or periodic: continue, stop or reseed (default continue)", "ACTION");
+    opts.optflag("", "stop-stable", "same as --on-settle stop");
+    opts.optopt("", "max-period", "longest cycle to look for, in steps (default 256)", "N");
+    opts.optopt("", "history", "generations kept to step back through with numpad -, 0 for none (default 1000)", "N");
+    opts.optflag("", "merge-faces", "merge coplanar faces of exported meshes into larger ones");
+    opts.optopt("", "snapshot", "file F5 saves the world to and F9 restores it from (default world.snap)", "FILE");
+    opts.optopt("", "final", "write the final cells of a batch run to FILE, a cell list, Golly 3D .rle, MagicaVoxel .vox or .obj, .stl or .ply mesh", "FILE");
+    opts.optopt("", "population", "write the population series of a batch run to FILE", "FILE");
+    opts.optopt("", "stats", "write population, births, deaths, bounding box and centre of mass of every generation to FILE", "FILE");
+    opts.optopt("", "stats-format", "csv or jsonl (default jsonl for .json and .jsonl files, csv otherwise)", "FORMAT");
+    opts.optflag("h", "help", "print this help");
+    opts
+}
+
+fn parse_dims(s: &str, n: usize) -> Option<Vec<usize>> {
+    let dims: Vec<_> = match s.split('x').map(|d| d.trim().parse::<usize>()).collect() {
+        Ok(dims) => dims,
+        Err(_) => return None,
+    };
+    match dims.len() {
+        1 => Some(vec![dims[0]; n]),
+        l if l == n => Some(dims),
+        _ => None,
+    }.and_then(|dims| if dims.iter().all(|&d| d > 0) { Some(dims) } else { None })
+}
+
+fn parse_region(s: &str) -> Option<Region> {
+    let mut parts = s.splitn(2, ':');
+    let kind = parts.next().unwrap();
+    let fraction = match parts.next().map(|f| f.parse::<f64>()) {
+        Some(Ok(f)) if 0.0 < f && f <= 1.0 => Some(f),
+        Some(_) => return None,
+        None => None,
+    };
+    match kind {
+        "whole" if fraction.is_none() => Some(Region::Whole),
+        "box" => Some(Region::Box(fraction.unwrap_or(0.75))),
+        "sphere" => Some(Region::Sphere(fraction.unwrap_or(1.0))),
+        _ => None,
+    }
+}
+
+fn parse_stamp(s: &str) -> Result<(&'static patterns::Pattern, Option<U3d>), String> {
+    let mut parts = s.splitn(2, '@');
+    let name = parts.next().unwrap();
+    let pattern = try!(patterns::find(name).ok_or(format!("unknown pattern: {}, see --list-patterns", name)));
+    let at = match parts.next() {
+        Some(p) => match p.split(',').map(|c| c.trim().parse::<usize>()).collect::<Result<Vec<_>, _>>() {
+            Ok(ref c) if c.len() == 3 => Some((c[0], c[1], c[2])),
+            _ => return Err(format!("invalid --stamp position: {}, expected X,Y,Z", p)),
+        },
+        None => None,
+    };
+    Ok((pattern, at))
+}
+
+fn pattern_list() -> String {
+    PATTERNS.iter().map(|p| {
+        let (xs, ys, zs) = p.size();
+        let kind = if p.is_spaceship() {
+            format!("spaceship p{}", p.period)
+        } else if p.period == 1 {
+            String::from("still life")
+        } else {
+            format!("oscillator p{}", p.period)
+        };
+        format!("{:14} {:8} {:16} {}x{}x{}  {}", p.name, p.rule, kind, xs, ys, zs, p.description)
+    }).collect::<Vec<_>>().join("\n")
+}
+
+fn parse_num<T: std::str::FromStr>(matches: &getopts::Matches, name: &str) -> Result<Option<T>, String> {
+    match matches.opt_str(name) {
+        Some(s) => s.parse::<T>().map(Some).map_err(|_| format!("invalid --{}: {}", name, s)),
+        None => Ok(None),
+    }
+}
+
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let opts = opts();
+    let matches = try!(opts.parse(args).map_err(|e| e.to_string()));
+    if matches.opt_present("h") {
+        let program = std::env::args().next().unwrap_or(String::from("game_of_life"));
+        return Ok(Command::Help(opts.usage(&format!("Usage: {} [options]", program))));
+    }
+    if matches.opt_present("list-patterns") {
+        return Ok(Command::Help(pattern_list()));
+    }
+    if !matches.free.is_empty() {
+        return Err(format!("unexpected argument: {}", matches.free[0]));
+    }
+
+    let mut options = Options::default();
+    if let Some(s) = matches.opt_str("size") {
+        let d = try!(parse_dims(&s, 3).ok_or(format!("invalid --size: {}", s)));
+        options.dim = (d[0], d[1], d[2]);
+    }
+    if let Some(s) = matches.opt_str("window") {
+        let d = try!(parse_dims(&s, 2).ok_or(format!("invalid --window: {}", s)));
+        options.window_size = (d[0] as u32, d[1] as u32);
+    }
+    options.asset_dir = matches.opt_str("assets");
+    if let Some(s) = matches.opt_str("rule") {
+        let neighbours = neighbourhood_size(options.dim);
+        options.rule = try!(Rule::parse_with_neighbourhood(&s, neighbours).map_err(|e| format!("{}\n  {}\n  {}^", e, s, " ".repeat(e.position))));
+    }
+    if let Some(s) = matches.opt_str("boundary") {
+        let b = try!(s.split(',').map(|b| b.trim().parse::<Boundary>()).collect::<Result<Vec<_>, _>>());
+        options.boundaries = match b.len() {
+            1 => [b[0]; 3],
+            3 => [b[0], b[1], b[2]],
+            _ => return Err(format!("invalid --boundary: {}, expected one or three values", s)),
+        };
+    }
+    options.seed = try!(parse_num(&matches, "seed"));
+    if let Some(d) = try!(parse_num::<f64>(&matches, "density")) {
+        if !(0.0 <= d && d <= 1.0) {
+            return Err(format!("--density must be within 0..1, got {}", d));
+        }
+        options.density = d;
+    }
+    if let Some(s) = matches.opt_str("region") {
+        options.region = try!(parse_region(&s).ok_or(format!("invalid --region: {}", s)));
+    }
+    if let Some(n) = try!(parse_num::<usize>(&matches, "skip")) {
+        options.lattice_skip = if n == 0 { None } else { Some(n) };
+    }
+    options.step_interval = try!(parse_num(&matches, "interval")).unwrap_or(options.step_interval);
+    options.threads = try!(parse_num(&matches, "threads")).unwrap_or(options.threads);
+    options.start_generation = try!(parse_num(&matches, "start-gen")).unwrap_or(0);
+    options.pattern = matches.opt_str("pattern");
+    if let Some(s) = matches.opt_str("vox-colours") {
+        let colours = s.split(',').map(|c| c.trim().parse::<u8>()).collect::<Result<Vec<_>, _>>();
+        options.vox_colours = Some(try!(colours.map_err(|_| format!("invalid --vox-colours: {}", s))));
+    }
+    for s in matches.opt_strs("stamp") {
+        options.stamps.push(try!(parse_stamp(&s)));
+    }
+    if !matches.opt_present("rule") {
+        if let Some(&(pattern, _)) = options.stamps.first() {
+            options.rule = pattern.rule();
+        }
+    }
+    options.script = matches.opt_str("script");
+    if let Some(s) = matches.opt_str("control") {
+        options.control = Some(try!(s.parse()));
+    }
+    if let Some(n) = try!(parse_num(&matches, "batch")) {
+        options.batch = Some(n);
+    }
+    if matches.opt_present("stop-stable") {
+        options.on_settle = OnSettle::Stop;
+    }
+    if let Some(s) = matches.opt_str("on-settle") {
+        options.on_settle = try!(s.parse());
+    }
+    if let Some(n) = try!(parse_num::<usize>(&matches, "max-period")) {
+        if n == 0 {
+            return Err(String::from("--max-period must be at least 1"));
+        }
+        options.max_period = n;
+    }
+    options.final_state = matches.opt_str("final");
+    options.merge_faces = matches.opt_present("merge-faces");
+    options.population_file = matches.opt_str("population");
+    options.history = try!(parse_num(&matches, "history")).unwrap_or(options.history);
+    if let Some(path) = matches.opt_str("snapshot") {
+        options.snapshot_path = path;
+    }
+    options.stats_file = matches.opt_str("stats");
+    if let Some(s) = matches.opt_str("stats-format") {
+        options.stats_format = Some(try!(s.parse()));
+    }
+    options.engine = match matches.opt_str("engine") {
+        None => Engine::Dense,
+        Some(ref e) if e == "dense" => Engine::Dense,
+        Some(ref e) if e == "sparse" => Engine::Sparse,
+        Some(ref e) if e == "hashlife" => Engine::HashLife,
+        Some(e) => return Err(format!("unknown --engine: {}", e)),
+    };
+    if let Some(k) = try!(parse_num::<u8>(&matches, "hash-step")) {
+        if k > hashlife::MAX_STEP_EXPONENT {
+            return Err(format!("--hash-step must be at most {}, got {}", hashlife::MAX_STEP_EXPONENT, k));
+        }
+        options.hash_step = k;
+    }
+    if options.engine != Engine::Dense {
+        let engine = if options.engine == Engine::Sparse { "sparse" } else { "hashlife" };
+        try!(check_unbounded(&options.rule).map_err(|e| format!("--engine {}: {}", engine, e)));
+    }
+    if options.batch.is_none() && options.engine != Engine::Dense {
+        return Err(String::from("the window shows dense worlds only, other engines need --batch"));
+    }
+    if options.batch.is_some() && options.control.is_some() {
+        return Err(String::from("--control needs the window, not --batch"));
+    }
+    if options.batch.is_none() && (options.final_state.is_some() || options.population_file.is_some()) {
+        return Err(String::from("--final and --population need --batch"));
+    }
+    Ok(Command::Run(options))
+}
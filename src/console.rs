@@ -0,0 +1,248 @@
+//! Line-oriented commands for driving a world from stdin or a script:
+//!
+//! ```text
+//! # comments and blank lines are skipped
+//! rule B5/S45
+//! clear
+//! load glider.rle 10 10 10
+//! set 3 4 5 1
+//! step 10
+//! stats
+//! save out.vox
+//! ```
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::str::FromStr;
+
+use cell_list;
+use mesh::{self, Mesh};
+use rle;
+use rule::Rule;
+use stats::{Format, StatsWriter, Tracker};
+use vox;
+use state::U3d;
+use world::{World, I3d};
+
+pub const HELP: &'static str = "\
+step [N]          step N generations, 1 if not given
+set X Y Z 0|1     kill or revive a cell
+rule RULE         change the rule, e.g. B5/S45
+clear             kill every cell
+load FILE [X Y Z] replace the world with a cell list, .rle or .vox, moving its corner to X Y Z
+save FILE         write the live cells as a cell list, .rle, .vox, .obj, .stl or .ply
+stats             print population, births and deaths since the last stats, bounds and centre
+pause, run        stop or resume stepping in the window
+jump N            go back or forward to generation N in the window's history
+quit              stop reading commands
+help              show this list";
+
+const NAMES: &'static [&'static str] = &[
+    "step", "set", "rule", "clear", "load", "save", "stats", "pause", "run", "jump", "quit", "exit", "help",
+];
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    Step(u64),
+    Set(I3d, bool),
+    Rule(Rule),
+    Clear,
+    Load(String, Option<I3d>),
+    Save(String),
+    Stats,
+    Pause,
+    Run,
+    /// Goes to a generation kept in the window's history.
+    Jump(u64),
+    Quit,
+    Help,
+}
+
+fn parse_coords(words: &[&str]) -> Result<I3d, String> {
+    let c = try!(words.iter().map(|w| w.parse::<i64>()).collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid coordinates: {}", words.join(" "))));
+    Ok((c[0], c[1], c[2]))
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Command, String> {
+        let words: Vec<_> = s.split_whitespace().collect();
+        let (name, args) = match words.split_first() {
+            Some((name, args)) => (*name, args),
+            None => return Err(String::from("empty command")),
+        };
+        match (name, args.len()) {
+            ("step", 0) => Ok(Command::Step(1)),
+            ("step", 1) => args[0].parse().map(Command::Step).map_err(|_| format!("invalid count: {}", args[0])),
+            ("set", 4) => {
+                let alive = match args[3] {
+                    "0" => false,
+                    "1" => true,
+                    s => return Err(format!("expected 0 or 1, got {}", s)),
+                };
+                Ok(Command::Set(try!(parse_coords(&args[..3])), alive))
+            },
+            ("rule", 1) => Rule::parse(args[0]).map(Command::Rule).map_err(|e| format!("{}: {}", args[0], e)),
+            ("clear", 0) => Ok(Command::Clear),
+            ("load", 1) => Ok(Command::Load(args[0].to_string(), None)),
+            ("load", 4) => Ok(Command::Load(args[0].to_string(), Some(try!(parse_coords(&args[1..]))))),
+            ("save", 1) => Ok(Command::Save(args[0].to_string())),
+            ("stats", 0) => Ok(Command::Stats),
+            ("pause", 0) => Ok(Command::Pause),
+            ("run", 0) => Ok(Command::Run),
+            ("jump", 1) => args[0].parse().map(Command::Jump).map_err(|_| format!("invalid generation: {}", args[0])),
+            ("quit", 0) | ("exit", 0) => Ok(Command::Quit),
+            ("help", 0) => Ok(Command::Help),
+            _ if NAMES.contains(&name) => Err(format!("wrong arguments to {}, see help", name)),
+            _ => Err(format!("unknown command: {}, see help", name)),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Step(n) => write!(f, "step {}", n),
+            Command::Set((x, y, z), alive) => write!(f, "set {} {} {} {}", x, y, z, alive as u8),
+            Command::Rule(ref rule) => write!(f, "rule {}", rule),
+            Command::Clear => write!(f, "clear"),
+            Command::Load(ref path, None) => write!(f, "load {}", path),
+            Command::Load(ref path, Some((x, y, z))) => write!(f, "load {} {} {} {}", path, x, y, z),
+            Command::Save(ref path) => write!(f, "save {}", path),
+            Command::Stats => write!(f, "stats"),
+            Command::Pause => write!(f, "pause"),
+            Command::Run => write!(f, "run"),
+            Command::Jump(generation) => write!(f, "jump {}", generation),
+            Command::Quit => write!(f, "quit"),
+            Command::Help => write!(f, "help"),
+        }
+    }
+}
+
+/// Parses a line of a script; blank lines and `#` comments give `None`.
+pub fn parse_line(line: &str) -> Result<Option<Command>, String> {
+    let line = line.split('#').next().unwrap().trim();
+    if line.is_empty() {
+        Ok(None)
+    } else {
+        line.parse().map(Some)
+    }
+}
+
+/// Runs commands against a world, keeping what `stats` needs between
+/// calls.
+#[derive(Default)]
+pub struct Console {
+    tracker: Tracker,
+}
+
+impl Console {
+    pub fn new() -> Console {
+        Console::default()
+    }
+
+    /// Carries out `command` and returns the text to show for it, if any.
+    /// `Pause`, `Run` and `Quit` are left to the caller and do nothing here.
+    pub fn execute<W: World + ?Sized>(&mut self, world: &mut W, command: &Command) -> Result<String, String> {
+        match *command {
+            Command::Step(n) => {
+                for _ in 0..n {
+                    world.step();
+                }
+                Ok(format!("generation {}, population {}", world.generation(), world.population()))
+            },
+            Command::Set(p, alive) => {
+                if !world.contains(p) {
+                    return Err(format!("cell {},{},{} lies outside the world", p.0, p.1, p.2));
+                }
+                world.set(p, alive);
+                Ok(String::new())
+            },
+            Command::Rule(rule) => {
+                try!(world.check_rule(&rule));
+                world.set_rule(rule);
+                Ok(String::new())
+            },
+            Command::Clear => {
+                clear(world);
+                Ok(String::new())
+            },
+            Command::Load(ref path, at) => {
+                let (cells, rule) = try!(read_cells(path, at).map_err(|e| format!("{}: {}", path, e)));
+                if let Some(&(x, y, z)) = cells.iter().find(|&&p| !world.contains(p)) {
+                    return Err(format!("{}: cell {},{},{} lies outside the world", path, x, y, z));
+                }
+                if let Some(ref rule) = rule {
+                    try!(world.check_rule(rule).map_err(|e| format!("{}: {}", path, e)));
+                }
+                clear(world);
+                for &p in cells.iter() {
+                    world.set(p, true);
+                }
+                if let Some(rule) = rule {
+                    world.set_rule(rule);
+                }
+                Ok(format!("loaded {} cells", cells.len()))
+            },
+            Command::Save(ref path) => {
+                try!(save(&*world, path).map_err(|e| format!("{}: {}", path, e)));
+                Ok(format!("saved {} cells", world.population()))
+            },
+            Command::Stats => {
+                let mut line = Vec::new();
+                try!(StatsWriter::new(&mut line, Format::JsonLines).write(&self.tracker.record(&*world))
+                    .map_err(|e| e.to_string()));
+                Ok(String::from_utf8_lossy(&line).trim_right().to_string())
+            },
+            Command::Help => Ok(String::from(HELP)),
+            Command::Jump(_) => Err(String::from("jump needs the history kept by the window")),
+            Command::Pause | Command::Run | Command::Quit => Ok(String::new()),
+        }
+    }
+}
+
+fn clear<W: World + ?Sized>(world: &mut W) {
+    let cells: Vec<_> = world.live_cells().collect();
+    for p in cells {
+        world.set(p, false);
+    }
+}
+
+/// Reads the cells of a file and its rule, if it has one. The corner of
+/// their bounding box is moved to `at` if given.
+fn read_cells(path: &str, at: Option<I3d>) -> io::Result<(Vec<I3d>, Option<Rule>)> {
+    let file = BufReader::new(try!(File::open(path)));
+    let relative = |cells: Vec<U3d>| cells.into_iter().map(|(x, y, z)| (x as i64, y as i64, z as i64)).collect();
+    let (corner, cells, rule): (I3d, Vec<I3d>, _) = if path.ends_with(".rle") {
+        let pattern = try!(rle::read(file));
+        (pattern.position.unwrap_or((0, 0, 0)), relative(pattern.cells), pattern.rule)
+    } else if path.ends_with(".vox") {
+        ((0, 0, 0), relative(try!(vox::read(file)).cells(None)), None)
+    } else {
+        let cells = try!(cell_list::read_world(file));
+        let corner = cells.iter().fold(None, |low: Option<I3d>, &(x, y, z)| Some(match low {
+            None => (x, y, z),
+            Some((lx, ly, lz)) => (lx.min(x), ly.min(y), lz.min(z)),
+        })).unwrap_or((0, 0, 0));
+        let cells = cells.into_iter().map(|(x, y, z)| (x - corner.0, y - corner.1, z - corner.2)).collect();
+        (corner, cells, None)
+    };
+    let (cx, cy, cz) = at.unwrap_or(corner);
+    Ok((cells.into_iter().map(|(x, y, z)| (cx + x, cy + y, cz + z)).collect(), rule))
+}
+
+fn save<W: World + ?Sized>(world: &W, path: &str) -> io::Result<()> {
+    let file = BufWriter::new(try!(File::create(path)));
+    if let Some(format) = mesh::Format::from_path(path) {
+        Mesh::from_world(world, false).write(format, file)
+    } else if path.ends_with(".vox") {
+        vox::write_world(world, vox::DEFAULT_COLOUR, file)
+    } else if path.ends_with(".rle") {
+        rle::write_world(world, None, &[], file)
+    } else {
+        cell_list::write_world(world, file)
+    }
+}
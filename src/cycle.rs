@@ -0,0 +1,111 @@
+//! Detects worlds that died out, stopped changing or entered a cycle, by
+//! remembering a hash of every recent generation.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use world::World;
+
+/// How a world settled.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Extinct { since: u64 },
+    /// Still life: every step leaves the world as it is.
+    Static { since: u64 },
+    /// The world at `since + period` equals the one at `since`.
+    Periodic { period: u64, since: u64 },
+}
+
+impl Outcome {
+    /// First generation of the final state or cycle.
+    pub fn since(&self) -> u64 {
+        match *self {
+            Outcome::Extinct { since } | Outcome::Static { since } | Outcome::Periodic { since, .. } => since,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Outcome::Extinct { since } => write!(f, "extinct since generation {}", since),
+            Outcome::Static { since } => write!(f, "static since generation {}", since),
+            Outcome::Periodic { period, since } =>
+                write!(f, "periodic with period {} since generation {}", period, since),
+        }
+    }
+}
+
+/// Table of the hashes of the last `capacity` generations seen. Periods up
+/// to `capacity` steps are found. Worlds are compared by their 64-bit
+/// `World::state_hash` alone, so a collision could be mistaken for a cycle.
+pub struct CycleDetector {
+    /// Hash to the first generation it was seen at.
+    seen: HashMap<u64, u64>,
+    order: VecDeque<u64>,
+    capacity: usize,
+    outcome: Option<Outcome>,
+}
+
+impl CycleDetector {
+    pub fn new(capacity: usize) -> CycleDetector {
+        assert!(capacity > 0, "the history must hold at least one generation");
+        CycleDetector {
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            capacity: capacity,
+            outcome: None,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// What the world settled into, once found.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// Forgets the history, for when the world was edited or reseeded.
+    pub fn reset(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+        self.outcome = None;
+    }
+
+    /// Records the current generation of `world`. Returns the outcome the
+    /// first time the world is found to have settled, `None` otherwise.
+    pub fn observe<W: World + ?Sized>(&mut self, world: &W) -> Option<Outcome> {
+        if self.outcome.is_some() {
+            return None;
+        }
+        let generation = world.generation();
+        let hash = world.state_hash();
+        if let Some(&since) = self.seen.get(&hash) {
+            let outcome = if world.population() == 0 {
+                Outcome::Extinct { since: since }
+            } else if world.is_static() {
+                Outcome::Static { since: since }
+            } else {
+                Outcome::Periodic { period: generation - since, since: since }
+            };
+            self.outcome = Some(outcome);
+            return self.outcome;
+        }
+
+        self.seen.insert(hash, generation);
+        self.order.push_back(hash);
+        if self.order.len() > self.capacity {
+            let oldest = self.order.pop_front().unwrap();
+            self.seen.remove(&oldest);
+        }
+        None
+    }
+}
+
+impl Default for CycleDetector {
+    fn default() -> CycleDetector {
+        CycleDetector::new(256)
+    }
+}
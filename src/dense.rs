@@ -0,0 +1,94 @@
+//! Straightforward one-`bool`-per-cell world. Slow, but simple enough to
+//! serve as the reference `State` is tested against.
+
+extern crate std;
+
+use boundary::{Boundary, Boundaries};
+use rule::Rule;
+use state::{U3d, i2p, p2i};
+
+pub struct DenseState {
+    dim: U3d,
+    world: Vec<bool>,
+    old_world: Vec<bool>,
+    rule: Rule,
+    boundaries: Boundaries,
+}
+
+impl DenseState {
+    pub fn new((xs, ys, zs): U3d, rule: Rule) -> DenseState {
+        let world = vec![false; xs * ys * zs];
+        DenseState {
+            dim: (xs, ys, zs),
+            old_world: world.clone(),
+            world: world,
+            rule: rule,
+            boundaries: [Boundary::Periodic; 3],
+        }
+    }
+
+    pub fn dim(&self) -> U3d {
+        self.dim
+    }
+
+    pub fn set_boundaries(&mut self, boundaries: Boundaries) {
+        self.boundaries = boundaries;
+    }
+
+    pub fn is_alive(&self, p: U3d) -> bool {
+        self.world[p2i(self.dim, p)]
+    }
+
+    pub fn set(&mut self, p: U3d, alive: bool) {
+        let idx = p2i(self.dim, p);
+        self.world[idx] = alive;
+    }
+
+    pub fn population(&self) -> usize {
+        self.world.iter().filter(|c| **c).count()
+    }
+
+    pub fn live_cells<'a>(&'a self) -> Box<Iterator<Item = U3d> + 'a> {
+        let dim = self.dim;
+        Box::new(
+            self.world.iter()
+                .enumerate()
+                .filter(|&(_, alive)| *alive)
+                .map(move |(i, _)| i2p(dim, i))
+        )
+    }
+
+    #[inline]
+    fn old_cell(&self, (x, y, z): (isize, isize, isize)) -> bool {
+        let (xs, ys, zs) = self.dim;
+        let (bx, by, bz) = (self.boundaries[0], self.boundaries[1], self.boundaries[2]);
+        match (bx.resolve(x, xs), by.resolve(y, ys), bz.resolve(z, zs)) {
+            (Some(x), Some(y), Some(z)) => self.old_world[p2i(self.dim, (x, y, z))],
+            (x, y, z) =>
+                (x.is_some() || bx.wall_alive())
+                && (y.is_some() || by.wall_alive())
+                && (z.is_some() || bz.wall_alive()),
+        }
+    }
+
+    pub fn step_forward(&mut self) {
+        std::mem::swap(&mut self.world, &mut self.old_world);
+        let (xs, ys, zs) = self.dim;
+        let offsets = |size: usize| if size == 1 { 0..1 } else { -1..2 };
+        for i in 0..(xs * ys * zs) {
+            let (x, y, z) = i2p(self.dim, i);
+            let (x, y, z) = (x as isize, y as isize, z as isize);
+            let mut neighbours = 0;
+            for dx in offsets(xs) {
+                for dy in offsets(ys) {
+                    for dz in offsets(zs) {
+                        if !(dx == 0 && dy == 0 && dz == 0) {
+                            neighbours += self.old_cell((x + dx, y + dy, z + dz)) as u32;
+                        }
+                    }
+                }
+            }
+            self.world[i] = self.rule.next_state(self.old_world[i], neighbours);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Everything that can keep the program from starting, or a batch run from
+//! finishing.
+
+// A headless build has no window, so the errors opening one never occur.
+#![cfg_attr(feature = "headless", allow(dead_code))]
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum StartupError {
+    /// The command line could not be understood.
+    Usage(String),
+    /// The world, a script, the statistics file or the control server could
+    /// not be set up, or a batch run failed.
+    Setup(String),
+    /// An asset exists but could not be read.
+    Io { path: String, error: io::Error },
+    /// A mesh file holds no usable geometry.
+    Mesh { path: String, message: String },
+    /// The window, its OpenGL context or an object on it could not be
+    /// created.
+    Context(String),
+    /// A shader program failed to compile or link; `log` is the driver's
+    /// output.
+    Shader { files: String, log: String },
+}
+
+impl StartupError {
+    /// Wraps the failure to create an OpenGL object, such as a buffer.
+    pub fn context<E: fmt::Debug>(what: &str, error: E) -> StartupError {
+        StartupError::Context(format!("cannot create {}: {:?}", what, error))
+    }
+}
+
+impl From<String> for StartupError {
+    fn from(message: String) -> StartupError {
+        StartupError::Setup(message)
+    }
+}
+
+impl fmt::Display for StartupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StartupError::Usage(ref message) | StartupError::Setup(ref message) => f.write_str(message),
+            StartupError::Io { ref path, ref error } => write!(f, "cannot read {}: {}", path, error),
+            StartupError::Mesh { ref path, ref message } => write!(f, "cannot load mesh {}: {}", path, message),
+            StartupError::Context(ref message) => write!(f, "OpenGL: {}", message),
+            StartupError::Shader { ref files, ref log } =>
+                write!(f, "cannot build the shader program from {}:\n{}", files, log.trim_right()),
+        }
+    }
+}
+
+impl Error for StartupError {
+    fn description(&self) -> &str {
+        match *self {
+            StartupError::Usage(_) => "invalid command line",
+            StartupError::Setup(_) => "setup failed",
+            StartupError::Io { .. } => "cannot read an asset",
+            StartupError::Mesh { .. } => "invalid mesh",
+            StartupError::Context(_) => "OpenGL setup failed",
+            StartupError::Shader { .. } => "shader compilation failed",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            StartupError::Io { ref error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,464 @@
+//! HashLife for 3D: the world is an octree of canonicalised (hash-consed)
+//! nodes, and the future of every node is memoised, so repetitive patterns
+//! can be advanced by huge powers of two in little time.
+//!
+//! A node of level `n` is a cube of `2^n` cells a side. Its result is its
+//! central cube of level `n - 1`, advanced by up to `2^(n - 2)` generations.
+
+extern crate std;
+
+use std::collections::HashMap;
+
+use rule::Rule;
+use world::{self, World, I3d};
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// Nodes are collected once there are more than this many.
+const DEFAULT_NODE_LIMIT: usize = 1 << 22;
+
+/// Largest `k` of a step of `2^k` generations; the root it needs is then at
+/// most `MAX_LEVEL`.
+pub const MAX_STEP_EXPONENT: u8 = 60;
+
+/// The root never grows beyond this level, whose cells still have `i64`
+/// coordinates.
+const MAX_LEVEL: u8 = 63;
+
+struct Node {
+    /// Octants indexed by `octant(x, y, z)`.
+    children: [NodeId; 8],
+    level: u8,
+    population: u64,
+}
+
+#[inline]
+fn octant(x: usize, y: usize, z: usize) -> usize {
+    x << 2 | y << 1 | z
+}
+
+/// Cells of a node as a 4x4x4 grid of its grandchildren.
+type Grid = [[[NodeId; 4]; 4]; 4];
+
+pub struct HashLife {
+    nodes: Vec<Node>,
+    index: HashMap<[NodeId; 8], NodeId>,
+    /// Node advanced by 2^k generations, keyed by node and k.
+    memo: HashMap<(NodeId, u8), NodeId>,
+    /// The empty node of every level built so far.
+    empty: Vec<NodeId>,
+    /// Covers `[-2^(level-1), 2^(level-1))` along every axis.
+    root: NodeId,
+    rule: Rule,
+    generation: u64,
+    step_exponent: u8,
+    changed: bool,
+    node_limit: usize,
+}
+
+impl HashLife {
+    /// # Panics
+    ///
+    /// Panics if the rule gives birth with 0 neighbours, which would fill
+    /// all of space.
+    pub fn new(rule: Rule) -> HashLife {
+        assert!(!rule.next_state(false, 0), "B0 rules cannot be simulated in an unbounded world");
+        let leaf = |population| Node { children: [DEAD; 8], level: 0, population: population };
+        let mut life = HashLife {
+            nodes: vec![leaf(0), leaf(1)],
+            index: HashMap::new(),
+            memo: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            rule: rule,
+            generation: 0,
+            step_exponent: 0,
+            changed: true,
+            node_limit: DEFAULT_NODE_LIMIT,
+        };
+        life.root = life.empty(3);
+        life
+    }
+
+    pub fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    /// `World::step` advances by `2^k` generations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is above `MAX_STEP_EXPONENT`.
+    pub fn set_step_exponent(&mut self, k: u8) {
+        assert!(k <= MAX_STEP_EXPONENT, "steps of 2^{} generations are too long", k);
+        self.step_exponent = k;
+    }
+
+    pub fn step_exponent(&self) -> u8 {
+        self.step_exponent
+    }
+
+    /// Number of distinct nodes currently stored.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Drops memoised results and unreachable nodes once more than `limit`
+    /// nodes are stored.
+    pub fn set_node_limit(&mut self, limit: usize) {
+        self.node_limit = limit;
+    }
+
+    /// Population as a `u64`, which cannot overflow for huge patterns.
+    pub fn population_u64(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    #[inline]
+    fn level(&self, id: NodeId) -> u8 {
+        self.nodes[id as usize].level
+    }
+
+    #[inline]
+    fn children(&self, id: NodeId) -> [NodeId; 8] {
+        self.nodes[id as usize].children
+    }
+
+    /// The canonical node with the given octants.
+    fn join(&mut self, children: [NodeId; 8]) -> NodeId {
+        if let Some(&id) = self.index.get(&children) {
+            return id;
+        }
+        let level = self.level(children[0]) + 1;
+        let population = children.iter().map(|&c| self.nodes[c as usize].population).sum();
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node { children: children, level: level, population: population });
+        self.index.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = *self.empty.last().unwrap();
+            let next = self.join([e; 8]);
+            self.empty.push(next);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Surrounds `id` with empty space, doubling its side around the centre.
+    fn pad(&mut self, id: NodeId) -> NodeId {
+        assert!(self.level(id) < MAX_LEVEL, "the pattern reaches beyond 2^62 cells from the origin");
+        let e = self.empty(self.level(id) - 1);
+        let children = self.children(id);
+        let mut out = [DEAD; 8];
+        for i in 0..8 {
+            let mut c = [e; 8];
+            c[7 - i] = children[i];
+            out[i] = self.join(c);
+        }
+        self.join(out)
+    }
+
+    fn grid(&self, id: NodeId) -> Grid {
+        let children = self.children(id);
+        let mut g = [[[DEAD; 4]; 4]; 4];
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    let child = children[octant(x / 2, y / 2, z / 2)];
+                    g[x][y][z] = self.children(child)[octant(x % 2, y % 2, z % 2)];
+                }
+            }
+        }
+        g
+    }
+
+    /// The node made of the 2x2x2 block of `g` starting at `(x, y, z)`.
+    fn sub(&mut self, g: &Grid, (x, y, z): (usize, usize, usize)) -> NodeId {
+        let mut c = [DEAD; 8];
+        for dx in 0..2 {
+            for dy in 0..2 {
+                for dz in 0..2 {
+                    c[octant(dx, dy, dz)] = g[x + dx][y + dy][z + dz];
+                }
+            }
+        }
+        self.join(c)
+    }
+
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let g = self.grid(id);
+        self.sub(&g, (1, 1, 1))
+    }
+
+    fn cell(&self, id: NodeId, level: u8, x: u64, y: u64, z: u64) -> bool {
+        if level == 0 {
+            return id == ALIVE;
+        }
+        let half = 1 << (level - 1);
+        let child = self.children(id)[octant((x >= half) as usize, (y >= half) as usize, (z >= half) as usize)];
+        self.cell(child, level - 1, x % half, y % half, z % half)
+    }
+
+    /// Next generation of the central 2x2x2 cells of a level 2 node.
+    fn base_result(&mut self, id: NodeId) -> NodeId {
+        let mut bits = 0u64;
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    if self.cell(id, 2, x, y, z) {
+                        bits |= 1 << (x * 16 + y * 4 + z);
+                    }
+                }
+            }
+        }
+        let mut out = [DEAD; 8];
+        for cx in 1..3 {
+            for cy in 1..3 {
+                for cz in 1..3 {
+                    let mut neighbours = 0;
+                    for x in cx - 1..cx + 2 {
+                        for y in cy - 1..cy + 2 {
+                            for z in cz - 1..cz + 2 {
+                                neighbours += (bits >> (x * 16 + y * 4 + z) & 1) as u32;
+                            }
+                        }
+                    }
+                    let alive = bits >> (cx * 16 + cy * 4 + cz) & 1 != 0;
+                    neighbours -= alive as u32;
+                    if self.rule.next_state(alive, neighbours) {
+                        out[octant(cx as usize - 1, cy as usize - 1, cz as usize - 1)] = ALIVE;
+                    }
+                }
+            }
+        }
+        self.join(out)
+    }
+
+    /// The centre of `id` advanced by `2^k` generations, `k <= level - 2`.
+    fn advance(&mut self, id: NodeId, k: u8) -> NodeId {
+        let n = self.level(id);
+        if self.nodes[id as usize].population == 0 {
+            return self.empty(n - 1);
+        }
+        if let Some(&r) = self.memo.get(&(id, k)) {
+            return r;
+        }
+        let result = if n == 2 {
+            self.base_result(id)
+        } else {
+            // 27 overlapping subcubes, each stepped by 2^(n-3) generations
+            // for a full step or just centred for a shorter one, then 8
+            // cubes built from those and advanced by the rest.
+            let g = self.grid(id);
+            let mut r = [[[DEAD; 3]; 3]; 3];
+            for i in 0..3 {
+                for j in 0..3 {
+                    for l in 0..3 {
+                        let s = self.sub(&g, (i, j, l));
+                        r[i][j][l] = if k == n - 2 { self.advance(s, n - 3) } else { self.centre(s) };
+                    }
+                }
+            }
+            let mut out = [DEAD; 8];
+            for a in 0..2 {
+                for b in 0..2 {
+                    for c in 0..2 {
+                        let mut m = [DEAD; 8];
+                        for dx in 0..2 {
+                            for dy in 0..2 {
+                                for dz in 0..2 {
+                                    m[octant(dx, dy, dz)] = r[a + dx][b + dy][c + dz];
+                                }
+                            }
+                        }
+                        let m = self.join(m);
+                        out[octant(a, b, c)] = self.advance(m, std::cmp::min(k, n - 3));
+                    }
+                }
+            }
+            self.join(out)
+        };
+        self.memo.insert((id, k), result);
+        result
+    }
+
+    /// Whether every live cell lies in the central cube of a quarter of the
+    /// root's side, made of the eight innermost nodes two levels below the
+    /// root's children.
+    fn centred(&self) -> bool {
+        let root = self.children(self.root);
+        let inner: u64 = (0..8).map(|i| {
+            let grandchild = self.children(root[i])[7 - i];
+            self.nodes[self.children(grandchild)[7 - i] as usize].population
+        }).sum();
+        inner == self.population_u64()
+    }
+
+    /// Advances the world by `2^k` generations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is above `MAX_STEP_EXPONENT`, or if the pattern grows
+    /// beyond 2^62 cells from the origin.
+    pub fn step_pow2(&mut self, k: u8) {
+        assert!(k <= MAX_STEP_EXPONENT, "steps of 2^{} generations are too long", k);
+        while self.level(self.root) < k + 3 || !self.centred() {
+            self.root = self.pad(self.root);
+        }
+        let before = self.root;
+        let root = self.root;
+        let after = self.advance(root, k);
+        let padded = self.pad(after);
+        self.changed = padded != before;
+        self.root = after;
+        self.generation += 1 << k;
+        if self.nodes.len() > self.node_limit {
+            self.collect_garbage();
+        }
+    }
+
+    /// Keeps only the nodes reachable from the root and forgets every
+    /// memoised result.
+    pub fn collect_garbage(&mut self) {
+        let old = std::mem::replace(&mut self.nodes, Vec::new());
+        self.nodes.push(Node { children: [DEAD; 8], level: 0, population: 0 });
+        self.nodes.push(Node { children: [DEAD; 8], level: 0, population: 1 });
+        self.index.clear();
+        self.memo.clear();
+        self.empty = vec![DEAD];
+        let mut remap = HashMap::new();
+        remap.insert(DEAD, DEAD);
+        remap.insert(ALIVE, ALIVE);
+        let root = self.root;
+        self.root = self.copy_from(&old, root, &mut remap);
+    }
+
+    fn copy_from(&mut self, old: &[Node], id: NodeId, remap: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        if let Some(&new) = remap.get(&id) {
+            return new;
+        }
+        let mut children = old[id as usize].children;
+        for c in children.iter_mut() {
+            *c = self.copy_from(old, *c, remap);
+        }
+        let new = self.join(children);
+        remap.insert(id, new);
+        new
+    }
+
+    fn set_cell(&mut self, id: NodeId, level: u8, x: u64, y: u64, z: u64, alive: bool) -> NodeId {
+        if level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+        let half = 1 << (level - 1);
+        let mut children = self.children(id);
+        let i = octant((x >= half) as usize, (y >= half) as usize, (z >= half) as usize);
+        children[i] = self.set_cell(children[i], level - 1, x % half, y % half, z % half, alive);
+        self.join(children)
+    }
+
+    /// Root-local coordinates of `p`, `None` if it lies outside the root.
+    fn local(&self, (x, y, z): I3d) -> Option<(u64, u64, u64)> {
+        let half = 1i64 << (self.level(self.root) - 1);
+        let inside = |c: i64| -half <= c && c < half;
+        if inside(x) && inside(y) && inside(z) {
+            Some(((x + half) as u64, (y + half) as u64, (z + half) as u64))
+        } else {
+            None
+        }
+    }
+
+    fn collect_cells(&self, id: NodeId, level: u8, (x, y, z): I3d, out: &mut Vec<I3d>) {
+        if self.nodes[id as usize].population == 0 {
+            return;
+        }
+        if level == 0 {
+            out.push((x, y, z));
+            return;
+        }
+        let half = 1i64 << (level - 1);
+        let children = self.children(id);
+        for dx in 0..2 {
+            for dy in 0..2 {
+                for dz in 0..2 {
+                    let origin = (x + dx as i64 * half, y + dy as i64 * half, z + dz as i64 * half);
+                    self.collect_cells(children[octant(dx, dy, dz)], level - 1, origin, out);
+                }
+            }
+        }
+    }
+}
+
+impl World for HashLife {
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// Forgets every memoised result.
+    ///
+    /// # Panics
+    ///
+    /// Panics on B0 rules, see `HashLife::new`.
+    fn set_rule(&mut self, rule: Rule) {
+        assert!(!rule.next_state(false, 0), "B0 rules cannot be simulated in an unbounded world");
+        self.rule = rule;
+        self.memo.clear();
+    }
+
+    fn check_rule(&self, rule: &Rule) -> Result<(), String> {
+        world::check_unbounded(rule)
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Advances by `2^step_exponent()` generations.
+    fn step(&mut self) {
+        let k = self.step_exponent;
+        self.step_pow2(k);
+    }
+
+    fn is_alive(&self, p: I3d) -> bool {
+        match self.local(p) {
+            Some((x, y, z)) => self.cell(self.root, self.level(self.root), x, y, z),
+            None => false,
+        }
+    }
+
+    fn set(&mut self, p: I3d, alive: bool) {
+        while self.local(p).is_none() {
+            if !alive {
+                return;
+            }
+            self.root = self.pad(self.root);
+        }
+        let (x, y, z) = self.local(p).unwrap();
+        let (root, level) = (self.root, self.level(self.root));
+        self.root = self.set_cell(root, level, x, y, z, alive);
+        self.changed = true;
+    }
+
+    fn population(&self) -> usize {
+        self.population_u64() as usize
+    }
+
+    fn live_cells<'a>(&'a self) -> Box<Iterator<Item = I3d> + 'a> {
+        let level = self.level(self.root);
+        let half = 1i64 << (level - 1);
+        let mut cells = Vec::new();
+        self.collect_cells(self.root, level, (-half, -half, -half), &mut cells);
+        Box::new(cells.into_iter())
+    }
+
+    /// True if the last step, of however many generations, left the world
+    /// unchanged.
+    fn is_static(&self) -> bool {
+        !self.changed
+    }
+}
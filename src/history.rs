@@ -0,0 +1,176 @@
+//! Bounded rewind history of a `State`. Only the words that changed between
+//! consecutive recorded states are kept, as xor deltas, so going back or
+//! forward is a matter of flipping them in the current world.
+
+use std::collections::VecDeque;
+
+use state::State;
+
+/// Difference between two consecutive recorded states.
+struct Delta {
+    older: u64,
+    newer: u64,
+    /// Index and xor of every word that differs.
+    changes: Vec<(u32, u64)>,
+}
+
+impl Delta {
+    fn apply(&self, words: &mut [u64]) {
+        for &(i, x) in self.changes.iter() {
+            words[i as usize] ^= x;
+        }
+    }
+}
+
+pub struct History {
+    /// Deltas leading up to the last recorded state, oldest first.
+    past: VecDeque<Delta>,
+    /// Deltas undone by stepping back, the next one to redo last.
+    future: Vec<Delta>,
+    /// Copy of the last recorded or restored world.
+    last: Vec<u64>,
+    last_generation: u64,
+    capacity: usize,
+}
+
+impl History {
+    /// Keeps up to `capacity` recorded states before the current one.
+    pub fn new(capacity: usize) -> History {
+        History {
+            past: VecDeque::new(),
+            future: Vec::new(),
+            last: Vec::new(),
+            last_generation: 0,
+            capacity: capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Remembers `state` as it is now; call after every step or edit.
+    ///
+    /// After stepping back, recording a state drops the generations that
+    /// were undone: the world branches off from there. A state older than
+    /// the last one recorded, such as a restored snapshot, starts the
+    /// history afresh.
+    pub fn record(&mut self, state: &State) {
+        let words = state.words();
+        if self.last.len() != words.len() || state.generation() < self.last_generation {
+            self.forget();
+            self.last = words.to_vec();
+            self.last_generation = state.generation();
+            return;
+        }
+        let changes: Vec<_> = self.last.iter().zip(words.iter()).enumerate()
+            .filter(|&(_, (a, b))| a != b)
+            .map(|(i, (a, b))| (i as u32, a ^ b))
+            .collect();
+        if changes.is_empty() && self.last_generation == state.generation() {
+            return;
+        }
+        self.past.push_back(Delta { older: self.last_generation, newer: state.generation(), changes: changes });
+        while self.past.len() > self.capacity {
+            self.past.pop_front();
+        }
+        self.future.clear();
+        self.last.copy_from_slice(words);
+        self.last_generation = state.generation();
+    }
+
+    /// Drops every recorded state.
+    pub fn forget(&mut self) {
+        self.past.clear();
+        self.future.clear();
+        self.last.clear();
+    }
+
+    /// Generation of the oldest state that can be restored.
+    pub fn oldest_generation(&self) -> u64 {
+        self.past.front().map_or(self.last_generation, |d| d.older)
+    }
+
+    /// Generation of the newest state that can be restored, later than the
+    /// current one after stepping back.
+    pub fn newest_generation(&self) -> u64 {
+        self.future.first().map_or(self.last_generation, |d| d.newer)
+    }
+
+    /// Number of states that can be stepped back to.
+    pub fn len(&self) -> usize {
+        self.past.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.past.is_empty()
+    }
+
+    /// Words held by the deltas, a measure of the memory used.
+    pub fn stored_words(&self) -> usize {
+        self.past.iter().chain(self.future.iter()).map(|d| d.changes.len()).sum()
+    }
+
+    /// Restores the state recorded before the current one. Unrecorded
+    /// changes to `state` are recorded first, as in `jump_to`. Returns false
+    /// if there is nothing to go back to.
+    pub fn step_back(&mut self, state: &mut State) -> bool {
+        self.sync(state);
+        match self.past.pop_back() {
+            Some(delta) => {
+                delta.apply(&mut self.last);
+                self.last_generation = delta.older;
+                self.future.push(delta);
+                self.restore(state);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Redoes a state undone by `step_back`. Returns false if there is none,
+    /// or `state` was changed since.
+    pub fn step_forward(&mut self, state: &mut State) -> bool {
+        if !self.is_current(state) {
+            return false;
+        }
+        match self.future.pop() {
+            Some(delta) => {
+                delta.apply(&mut self.last);
+                self.last_generation = delta.newer;
+                self.past.push_back(delta);
+                self.restore(state);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Goes back or forward to the latest recorded state of `generation`.
+    /// Returns false, leaving `state` alone, if that generation is not kept.
+    pub fn jump_to(&mut self, state: &mut State, generation: u64) -> bool {
+        self.sync(state);
+        if generation < self.oldest_generation() || generation > self.newest_generation() {
+            return false;
+        }
+        while state.generation() > generation && self.step_back(state) {}
+        while self.future.last().map_or(false, |d| d.newer <= generation) && self.step_forward(state) {}
+        state.generation() == generation
+    }
+
+    /// True if `state` is the last recorded or restored state.
+    fn is_current(&self, state: &State) -> bool {
+        self.last.as_slice() == state.words() && self.last_generation == state.generation()
+    }
+
+    fn sync(&mut self, state: &State) {
+        if !self.is_current(state) {
+            self.record(state);
+        }
+    }
+
+    fn restore(&self, state: &mut State) {
+        state.set_words(&self.last);
+        state.set_generation(self.last_generation);
+    }
+}
@@ -0,0 +1,261 @@
+//! Just enough JSON for the control protocol: values are parsed into and
+//! printed from `Json`.
+
+use std::fmt;
+
+/// Deepest nesting of arrays and objects accepted, well beyond what the
+/// protocol needs, so that hostile input cannot overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Members in the order they were given.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(s: &str) -> Result<Json, String> {
+        let mut parser = Parser { chars: s.chars().collect(), at: 0, depth: 0 };
+        let value = try!(parser.value());
+        parser.skip_whitespace();
+        if parser.at < parser.chars.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    /// Member `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref members) => members.iter().find(|m| m.0 == key).map(|m| &m.1),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Json::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match *self {
+            Json::Array(ref a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// The number if it is a whole one.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 9.007_199_254_740_992e15 => Some(n as i64),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if n.is_finite() => write!(f, "{}", n),
+            Json::Number(_) => f.write_str("null"),
+            Json::String(ref s) => write_string(f, s),
+            Json::Array(ref a) => {
+                try!(f.write_str("["));
+                for (i, v) in a.iter().enumerate() {
+                    try!(write!(f, "{}{}", if i == 0 { "" } else { "," }, v));
+                }
+                f.write_str("]")
+            },
+            Json::Object(ref members) => {
+                try!(f.write_str("{"));
+                for (i, &(ref k, ref v)) in members.iter().enumerate() {
+                    if i > 0 {
+                        try!(f.write_str(","));
+                    }
+                    try!(write_string(f, k));
+                    try!(write!(f, ":{}", v));
+                }
+                f.write_str("}")
+            },
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    try!(f.write_str("\""));
+    for c in s.chars() {
+        try!(match c {
+            '"' => f.write_str("\\\""),
+            '\\' => f.write_str("\\\\"),
+            '\n' => f.write_str("\\n"),
+            '\r' => f.write_str("\\r"),
+            '\t' => f.write_str("\\t"),
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32),
+            c => write!(f, "{}", c),
+        });
+    }
+    f.write_str("\"")
+}
+
+struct Parser {
+    chars: Vec<char>,
+    at: usize,
+    /// Values being parsed around the current one.
+    depth: usize,
+}
+
+impl Parser {
+    fn error(&self, msg: &str) -> String {
+        format!("invalid JSON at character {}: {}", self.at + 1, msg)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.at).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map_or(false, |c| c == ' ' || c == '\t' || c == '\n' || c == '\r') {
+            self.at += 1;
+        }
+    }
+
+    fn expect(&mut self, word: &str) -> Result<(), String> {
+        for c in word.chars() {
+            if self.peek() != Some(c) {
+                return Err(self.error(&format!("expected {}", word)));
+            }
+            self.at += 1;
+        }
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let value = self.inner_value();
+        self.depth -= 1;
+        value
+    }
+
+    fn inner_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('n') => self.expect("null").map(|_| Json::Null),
+            Some('t') => self.expect("true").map(|_| Json::Bool(true)),
+            Some('f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.at += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.at += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(try!(self.value()));
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(',') => self.at += 1,
+                        Some(']') => {
+                            self.at += 1;
+                            return Ok(Json::Array(items));
+                        },
+                        _ => return Err(self.error("expected , or ]")),
+                    }
+                }
+            },
+            Some('{') => {
+                self.at += 1;
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.at += 1;
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() != Some('"') {
+                        return Err(self.error("expected a member name"));
+                    }
+                    let key = try!(self.string());
+                    self.skip_whitespace();
+                    try!(self.expect(":"));
+                    members.push((key, try!(self.value())));
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(',') => self.at += 1,
+                        Some('}') => {
+                            self.at += 1;
+                            return Ok(Json::Object(members));
+                        },
+                        _ => return Err(self.error("expected , or }")),
+                    }
+                }
+            },
+            Some(c) if c == '-' || c.is_digit(10) => {
+                let start = self.at;
+                while self.peek().map_or(false, |c| c.is_digit(10) || "+-.eE".contains(c)) {
+                    self.at += 1;
+                }
+                let text: String = self.chars[start..self.at].iter().cloned().collect();
+                text.parse().map(Json::Number).map_err(|_| self.error("invalid number"))
+            },
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.at += 1;
+        let mut s = String::new();
+        loop {
+            let c = try!(self.peek().ok_or(self.error("unterminated string")));
+            self.at += 1;
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let e = try!(self.peek().ok_or(self.error("unterminated string")));
+                    self.at += 1;
+                    s.push(match e {
+                        '"' | '\\' | '/' => e,
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            let hex: String = self.chars[self.at..(self.at + 4).min(self.chars.len())].iter().cloned().collect();
+                            self.at += hex.len();
+                            let code = try!(u32::from_str_radix(&hex, 16).ok()
+                                .and_then(|code| if hex.len() == 4 { Some(code) } else { None })
+                                .ok_or(self.error("invalid \\u escape")));
+                            // Surrogate pairs are not needed by the protocol.
+                            ::std::char::from_u32(code).unwrap_or('\u{fffd}')
+                        },
+                        _ => return Err(self.error("invalid escape")),
+                    });
+                },
+                c => s.push(c),
+            }
+        }
+    }
+}
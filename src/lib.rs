@@ -0,0 +1,40 @@
+extern crate crossbeam;
+extern crate num_cpus;
+extern crate rand;
+
+pub mod batch;
+pub mod boundary;
+pub mod cell_list;
+pub mod console;
+pub mod cycle;
+pub mod dense;
+pub mod hashlife;
+pub mod history;
+pub mod json;
+pub mod mesh;
+pub mod packed;
+pub mod patterns;
+pub mod rle;
+pub mod rule;
+pub mod server;
+pub mod snapshot;
+pub mod soup;
+pub mod sparse;
+pub mod state;
+pub mod stats;
+pub mod vox;
+pub mod world;
+
+pub use boundary::{Boundary, Boundaries};
+pub use cycle::{CycleDetector, Outcome};
+pub use dense::DenseState;
+pub use hashlife::HashLife;
+pub use history::History;
+pub use mesh::Mesh;
+pub use patterns::Pattern;
+pub use rule::{Rule, RuleParseError, RuleParseErrorKind};
+pub use soup::{Soup, Region};
+pub use sparse::SparseWorld;
+pub use state::{State, U3d, i2p, p2i};
+pub use stats::GenerationStats;
+pub use world::{World, I3d};
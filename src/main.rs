@@ -1,435 +1,251 @@
+#[cfg(not(feature = "headless"))]
 #[macro_use]
 extern crate glium;
-extern crate rand;
+extern crate game_of_life;
+#[cfg(not(feature = "headless"))]
 extern crate nalgebra;
+#[cfg(not(feature = "headless"))]
 extern crate clock_ticks;
+#[cfg(not(feature = "headless"))]
 extern crate image;
 
+mod error;
+mod cli;
+#[cfg(not(feature = "headless"))]
 mod support;
+#[cfg(not(feature = "headless"))]
 mod camera;
+#[cfg(not(feature = "headless"))]
 mod transform;
+#[cfg(not(feature = "headless"))]
 mod objects;
-
-use glium::{Surface, glutin};
-use nalgebra::Vec3;
-use glium::backend::glutin_backend::GlutinFacade;
-use glium::glutin::{Event, ElementState, VirtualKeyCode};
-
-pub enum Action {
-    Stop,
-    Continue,
-}
-
-pub struct PerObjectState {
-    pos: Vec3<f32>,
-    scale_factor: f32,
-    show: bool,
-    color: Vec3<f32>,
+#[cfg(not(feature = "headless"))]
+mod window;
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter};
+use game_of_life::{State, SparseWorld, HashLife, World, Soup, Mesh, cell_list, rle, vox, batch, world};
+use game_of_life::console::{self, Command, Console};
+use game_of_life::mesh;
+use game_of_life::stats::{self, StatsWriter};
+use error::StartupError;
+
+fn soup(options: &cli::Options, seed: u64) -> Soup {
+    Soup::new(seed)
+        .with_density(options.density)
+        .with_region(options.region)
+        .with_lattice_skip(options.lattice_skip)
 }
 
-impl PerObjectState {
-    pub fn to_attr(&self) -> PerObjectAttr {
-        PerObjectAttr {
-            pos: self.pos.as_array().clone(),
-            scale_factor: self.scale_factor,
-            color: [
-                self.color.x,
-                self.color.y,
-                self.color.z,
-                if self.show { 1.0 } else { 0.0 }
-            ],
+fn build_state(options: &cli::Options) -> Result<State, String> {
+    // Snapshots bring their own size, rule and boundaries.
+    let snapshot = options.pattern.as_ref().map_or(false, |path| path.ends_with(".snap"));
+    let mut state = match options.pattern {
+        Some(ref path) if snapshot => {
+            let file = try!(File::open(path).map_err(|e| format!("cannot open {}: {}", path, e)));
+            try!(State::load_snapshot(BufReader::new(file)).map_err(|e| format!("{}: {}", path, e)))
+        },
+        Some(ref path) => {
+            let mut state = State::new(options.dim, options.rule);
+            let file = BufReader::new(try!(File::open(path).map_err(|e| format!("cannot open {}: {}", path, e))));
+            if path.ends_with(".vox") {
+                let colours = options.vox_colours.as_ref().map(|c| &c[..]);
+                try!(vox::load(&mut state, file, colours).map_err(|e| format!("{}: {}", path, e)));
+            } else if path.ends_with(".rle") {
+                let pattern = try!(rle::load(&mut state, file).map_err(|e| format!("{}: {}", path, e)));
+                for comment in pattern.comments.iter() {
+                    println!("{}", comment);
+                }
+            } else {
+                try!(cell_list::load(&mut state, file).map_err(|e| format!("{}: {}", path, e)));
+            }
+            state
+        },
+        None if !options.stamps.is_empty() => State::new(options.dim, options.rule),
+        None => {
+            let soup = soup(options, options.seed.unwrap_or_else(Soup::random_seed));
+            println!("seed: {} (density {}, region {})", soup.seed(), soup.density(), soup.region());
+            let mut state = State::new(options.dim, options.rule);
+            soup.fill(&mut state);
+            state
+        },
+    };
+    for &(pattern, at) in options.stamps.iter() {
+        if pattern.rule() != options.rule {
+            eprintln!("note: {} lives under {}, not {}", pattern.name, pattern.rule, options.rule);
         }
+        let at = at.unwrap_or_else(|| {
+            let ((xs, ys, zs), (px, py, pz)) = (state.dim(), pattern.size());
+            (xs.saturating_sub(px) / 2, ys.saturating_sub(py) / 2, zs.saturating_sub(pz) / 2)
+        });
+        try!(pattern.stamp(&mut state, at));
     }
+    if !snapshot {
+        state.set_boundaries(options.boundaries);
+    }
+    state.set_threads(options.threads);
+    for _ in 0..options.start_generation {
+        state.step_forward();
+    }
+    Ok(state)
 }
 
-#[derive(Copy, Clone)]
-pub struct PerObjectAttr {
-    pub pos: [f32; 3],
-    pub color: [f32; 4],
-    pub scale_factor: f32,
+/// Opens a script of console commands, or stdin for `-`.
+fn script(path: &str) -> Result<Box<BufRead + Send>, String> {
+    if path == "-" {
+        Ok(Box::new(BufReader::new(io::stdin())))
+    } else {
+        let file = try!(File::open(path).map_err(|e| format!("cannot open {}: {}", path, e)));
+        Ok(Box::new(BufReader::new(file)))
+    }
 }
 
-implement_vertex!(PerObjectAttr, pos, color, scale_factor);
+/// Runs a script against a world without a window, stopping at the first
+/// failing command.
+fn run_script(world: &mut World, path: &str) -> Result<(), String> {
+    let mut console = Console::new();
+    for (n, line) in try!(script(path)).lines().enumerate() {
+        let line = try!(line.map_err(|e| format!("cannot read {}: {}", path, e)));
+        let fail = |e: String| format!("{} line {}: {}", path, n + 1, e);
+        match try!(console::parse_line(&line).map_err(&fail)) {
+            None | Some(Command::Pause) | Some(Command::Run) => {},
+            Some(Command::Quit) => break,
+            Some(command) => {
+                let reply = try!(console.execute(world, &command).map_err(&fail));
+                if !reply.is_empty() {
+                    println!("{}", reply);
+                }
+            },
+        }
+    }
+    Ok(())
+}
 
-fn get_line() -> std::io::Result<String> {
-    let mut result = String::new();
-    match std::io::stdin().read_line(&mut result) {
-        Ok(n) => {
-            if n > 1 {
-                Ok(result)
+fn run_batch(state: State, generations: u64, options: &cli::Options) -> Result<(), String> {
+    let (dim, rule) = (state.dim(), *state.rule());
+    let mut soup = soup(options, state.seed().unwrap_or(0));
+    let mut world: Box<World> = match options.engine {
+        cli::Engine::Dense => Box::new(state),
+        cli::Engine::Sparse => {
+            try!(world::check_unbounded(state.rule()));
+            let mut sparse = SparseWorld::new(*state.rule());
+            for (x, y, z) in state.live_cells() {
+                sparse.set((x as i64, y as i64, z as i64), true);
             }
-            else {
-                Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Empty line"))
+            sparse.set_generation(state.generation());
+            Box::new(sparse)
+        },
+        cli::Engine::HashLife => {
+            try!(world::check_unbounded(state.rule()));
+            let mut hash = HashLife::new(*state.rule());
+            for (x, y, z) in state.live_cells() {
+                hash.set((x as i64, y as i64, z as i64), true);
             }
+            hash.set_generation(state.generation());
+            hash.set_step_exponent(options.hash_step);
+            Box::new(hash)
         },
-        Err(e) => Err(e),
+    };
+    if let Some(ref path) = options.script {
+        try!(run_script(&mut *world, path));
     }
-}
-
-type U3d = (usize, usize, usize);
-
-#[inline]
-fn i2p((_, ys, zs): U3d, idx: usize) -> U3d {
-    (idx / (zs * ys),  (idx / zs) % ys, idx % zs)
-}
-
-#[inline]
-fn p2i((_, ys, zs): U3d, (x, y, z): U3d) -> usize {
-    x * zs * ys + y * zs + z
-}
-
-struct State {
-    dim: U3d,
-    world: Vec<bool>,
-    old_world: Vec<bool>,
-    birth: Vec<u32>,
-    stay: Vec<u32>,
-}
-
-impl State {
-    pub fn new((xs, ys, zs): U3d) -> State {
-        let mut world = Vec::with_capacity(xs * ys * zs);
-
-        for x in 0..xs {
-            for y in 0..ys {
-                for z in 0..zs {
-                    let state =
-                        if xs / 8 <= x && x <= xs - xs / 8
-                            && ys / 8 <= y && y <= ys - ys / 8
-                            && zs / 8 <= z && z <= zs - zs / 8
-                            && x % 5 != 0 && y % 5 != 0 && z % 5 != 0 {
-                            rand::random()
-                        } else {
-                            false
-                        };
-                    world.push(state);
-                }
-            }
+    let config = batch::BatchConfig {
+        generations: generations,
+        on_settle: options.on_settle,
+        max_period: options.max_period,
+        record_stats: options.stats_file.is_some(),
+    };
+    let result = batch::run(&mut *world, &config, |world| {
+        soup = soup.next_seed();
+        let old: Vec<_> = world.live_cells().collect();
+        for p in old {
+            world.set(p, false);
         }
-
-        println!("birth:");
-        let b = get_line().unwrap_or(String::from("5"));
-        println!("stay:");
-        let s = get_line().unwrap_or(String::from("4 5"));
-        println!("{} | {}", b, s);
-        State {
-            dim: (xs, ys, zs),
-            old_world: world.clone(),
-            world: world,
-            birth: b.split_whitespace().map(|s| s.parse::<u32>().unwrap()).collect(),
-            stay: s.split_whitespace().map(|s| s.parse::<u32>().unwrap()).collect(),
+        let mut fresh = State::new(dim, rule);
+        soup.fill(&mut fresh);
+        for (x, y, z) in fresh.live_cells() {
+            world.set((x as i64, y as i64, z as i64), true);
         }
+    });
+    for outcome in result.settled.iter() {
+        println!("{}", outcome);
     }
-
-    pub fn get_initial_state(&self) -> Box<Iterator<Item = PerObjectState>> {
-        let (xs, ys, zs) = self.dim;
-        let world = self.world.clone();
-        Box::new(
-            (0..(xs * ys * zs)).map(move |i| {
-                let (x, y, z) = i2p((xs, ys, zs), i);
-                PerObjectState {
-                    pos: Vec3::new(
-                        (x as f32 - (xs - 1) as f32 / 2.0) * 15.0,
-                        (y as f32 - (ys - 1) as f32 / 2.0) * 15.0,
-                        (z as f32 - (zs - 1) as f32 / 2.0) * 15.0,
-                    ),
-                    scale_factor: 5.0,
-                    show: world[i],
-                    color: Vec3::new(0.9, 0.9, 0.9),
-                        // if rand::random() {
-                        //     Vec3::new(0.3 * rand::random::<f32>(), 0.05, 0.1)
-                        // } else {
-                        //     Vec3::new(0.1, 0.05, 0.3 * rand::random::<f32>())
-                        // },
-                }
-            })
-        )
+    println!("{} at generation {}, population {}", result.reason, world.generation(), world.population());
+
+    let create = |path: &String| File::create(path).map_err(|e| format!("cannot create {}: {}", path, e));
+    if let Some(ref path) = options.final_state {
+        let file = try!(create(path));
+        try!(if let Some(format) = mesh::Format::from_path(path) {
+            Mesh::from_world(&*world, options.merge_faces).write(format, BufWriter::new(file))
+        } else if path.ends_with(".vox") {
+            vox::write_world(&*world, vox::DEFAULT_COLOUR, file)
+        } else if path.ends_with(".rle") {
+            // Only the dense world has a grid of its own.
+            let (xs, ys, zs) = dim;
+            let grid = if options.engine == cli::Engine::Dense { Some(xs.max(ys).max(zs)) } else { None };
+            rle::write_world(&*world, grid, &[], file)
+        } else {
+            cell_list::write_world(&*world, file)
+        }.map_err(|e| format!("{}: {}", path, e)));
     }
-
-    pub fn up_to_actual_state(&self, state: &mut Vec<PerObjectState>) {
-        for (mut st, wld) in state.iter_mut().zip(self.world.iter()) {
-            st.show = *wld;
-        }
+    if let Some(ref path) = options.population_file {
+        try!(result.write_population(try!(create(path))).map_err(|e| format!("{}: {}", path, e)));
     }
-
-    #[inline]
-    fn rules(&self, alive: bool, neighbours: u32) -> bool {
-        match alive {
-            false => self.birth.iter().any(|x| *x == neighbours),
-            true => self.stay.iter().any(|x| *x == neighbours),
+    if let Some(mut writer) = try!(stats_writer(options)) {
+        for stats in result.stats.iter() {
+            try!(writer.write(stats).map_err(|e| format!("cannot write statistics: {}", e)));
         }
+        try!(writer.flush().map_err(|e| format!("cannot write statistics: {}", e)));
     }
+    Ok(())
+}
 
-    pub fn step_forward(&mut self) {
-        std::mem::swap(&mut self.world, &mut self.old_world);
-        let (xs, ys, zs) = self.dim;
-        unsafe {
-            for i in 0..(xs * ys * zs) {
-                let (x, y, z) = i2p(self.dim, i);
-                let (x, y, z) = (x + 1, y + 1, z + 1);
-                let neighbours = {
-                    let mut neib = 0;
-                    for mut dx in (x - 1)..(x + 2) {
-                        for mut dy in (y - 1)..(y + 2) {
-                            for mut dz in (z - 1)..(z + 2) {
-                                if xs > 2 {
-                                    if dx == 0 { dx = xs; }
-                                    if dx == xs + 1 { dx = 1; }
-                                }
-
-                                if ys > 2 {
-                                    if dy == 0 { dy = ys; }
-                                    if dy == ys + 1 { dy = 1; }
-                                }
-
-                                if zs > 2 {
-                                     if dz == 0 { dz = zs; }
-                                     if dz == zs + 1 { dz = 1; }
-                                }
-
-                                if !(dx == x && dy == y && dz == z) {
-                                    neib += *self.old_world.get_unchecked(p2i(self.dim, (dx - 1, dy - 1, dz - 1))) as u32;
-                                }
-                            }
-                        }
-                    }
-                    neib
-                };
-                *self.world.get_unchecked_mut(i) = self.rules(*self.old_world.get_unchecked(i), neighbours);
-            }
-        }
+fn stats_writer(options: &cli::Options) -> Result<Option<StatsWriter<BufWriter<File>>>, String> {
+    match options.stats_file {
+        Some(ref path) => {
+            let file = try!(File::create(path).map_err(|e| format!("cannot create {}: {}", path, e)));
+            let format = options.stats_format.unwrap_or_else(|| stats::Format::from_path(path));
+            Ok(Some(StatsWriter::new(BufWriter::new(file), format)))
+        },
+        None => Ok(None),
     }
 }
 
-#[derive(Copy, Clone)]
-struct Vertex {
-    position: [f32; 2],
+/// Sets everything up from the command line, then runs the batch or the
+/// window until it closes.
+fn run() -> Result<(), StartupError> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match try!(cli::parse(&args).map_err(StartupError::Usage)) {
+        cli::Command::Run(options) => options,
+        cli::Command::Help(usage) => {
+            println!("{}", usage);
+            return Ok(());
+        },
+    };
+    let state = try!(build_state(&options));
+    if let Some(generations) = options.batch {
+        try!(run_batch(state, generations, &options));
+        return Ok(());
+    }
+    open_window(state, &options)
 }
 
-implement_vertex!(Vertex, position);
-
-struct Applicaton {
-    display: GlutinFacade,
-    main_group: objects::InstancedObjects<PerObjectAttr>,
-    main_program: glium::Program,
-    background_program: glium::Program,
-    background_vb: glium::VertexBuffer<Vertex>,
-    background_ib: glium::IndexBuffer<u16>,
-    camera: camera::PerspectiveCamera,
-    state: State,
-    time_from_start: f32,
-    angle: f64,
-    r: f32,
+#[cfg(not(feature = "headless"))]
+fn open_window(state: State, options: &cli::Options) -> Result<(), StartupError> {
+    window::run(state, options)
 }
 
-impl Applicaton {
-    fn new(state: State) -> Applicaton {
-        use glium::DisplayBuild;
-
-        let display = glutin::WindowBuilder::new()
-            .with_depth_buffer(24)
-            .build_glium()
-            .unwrap();
-
-        let object_group = objects::InstancedObjects::new(
-            &display,
-            support::read_from_obj(&display, "support/cube.obj", true).unwrap(),
-            state.get_initial_state()
-                .map(|s| s.to_attr())
-                .collect()
-        );
-
-        let program = glium::Program::from_source(
-            &display,
-            &support::read_file_content("../shaders/main.vs").unwrap(),
-            &support::read_file_content("../shaders/main.fs").unwrap(),
-            None
-        ).unwrap();
-
-        let bg_program = glium::Program::from_source(
-            &display,
-            &support::read_file_content("../shaders/proc_tex.vs").unwrap(),
-            &support::read_file_content("../shaders/proc_tex.fs").unwrap(),
-            None
-        ).unwrap();
-
-        let background_vb = glium::VertexBuffer::new(
-            &display,
-            &[
-                Vertex { position: [-1.0, -1.0]},
-                Vertex { position: [-1.0,  1.0]},
-                Vertex { position: [ 1.0,  1.0]},
-                Vertex { position: [ 1.0, -1.0]}
-            ]
-        ).unwrap();
-
-        let background_ib = glium::IndexBuffer::new(
-            &display, glium::index::PrimitiveType::TriangleStrip, &[1 as u16, 2, 0, 3]
-        ).unwrap();
-
-        let r = 1500.0;
-        Applicaton {
-            display: display,
-            main_group: object_group,
-            main_program: program,
-            background_program: bg_program,
-            background_vb: background_vb,
-            background_ib: background_ib,
-            time_from_start: 0.0,
-            state: state,
-            angle: 0.0,
-            r: r,
-            camera: camera::PerspectiveCamera::new()
-                .with_fov(60)
-                .with_position(Vec3::new(0.0, 0.0, r))
-                .with_zfar(5000.0),
-        }
-    }
-
-    fn main_loop(&mut self) {
-        let params = glium::DrawParameters {
-            depth_test: glium::DepthTest::IfLess,
-            depth_write: true,
-            .. Default::default()
-        };
-
-        let mut transforms = self.state.get_initial_state().collect();
-        let mut last_step_time = clock_ticks::precise_time_ms();
-        let mut last_up_time = last_step_time;
-        let mut last_frame_time = last_step_time;
-        let mut frames = 0;
-        'main_loop: loop {
-            frames += 1;
-            const STEP_INTERVAL: u64 = 500;
-
-            let current_time = clock_ticks::precise_time_ms();
-            let dt = current_time - last_step_time;
-            let frame_dt = current_time - last_frame_time;
-            last_frame_time = current_time;
-            self.time_from_start += frame_dt as f32 / 1000.0;
-
-            if dt > STEP_INTERVAL {
-                self.state.step_forward();
-                self.state.up_to_actual_state(&mut transforms);
-                self.update_state_buffer(transforms.iter());
-                last_step_time = clock_ticks::precise_time_ms();
-            }
-
-            self.redraw_scene(self.display.draw(), &params);
-
-            if let Action::Stop = self.process_events() {
-                break 'main_loop;
-            }
-            let up_dt = clock_ticks::precise_time_ms() - last_up_time;
-            if up_dt > 1000 {
-                println!("{ft}: {fps}", ft=(up_dt as f32 / frames as f32), fps=(frames as f32 / up_dt as f32 * 1000.0));
-                last_up_time = clock_ticks::precise_time_ms();
-                frames = 0;
-            }
-        }
-    }
-
-    fn update_state_buffer<'a, I>(&mut self, storage_iter: I)
-        where I: Iterator<Item = &'a PerObjectState> {
-        self.main_group.update_per_instance_buffer(|ref mut m|
-            for (transf, mat) in storage_iter.zip(m.iter_mut()) {
-                *mat = transf.to_attr();
-            }
-        );
-    }
-
-    fn redraw_scene(&self, mut target: glium::Frame, params: &glium::DrawParameters) {
-        let (x_size, y_size) = target.get_dimensions();
-        let resolution = nalgebra::Vec2::new(x_size as f32, y_size as f32);
-        target.clear_color_and_depth((0.0, 0.0, 0.0, 0.0), 1.0);
-        target.draw(
-            &self.background_vb,
-            &self.background_ib,
-            &self.background_program,
-            &uniform!{
-                u_time: self.time_from_start,
-                u_resolution: resolution,
-            },
-            &glium::DrawParameters {
-                depth_test: glium::DepthTest::Overwrite,
-                depth_write: false,
-                .. Default::default()
-            }
-        ).unwrap();
-
-        target.draw(
-            self.main_group.get_vertices_data(),
-            self.main_group.get_indices_data(),
-            &self.main_program,
-            &uniform!{ mvp: self.camera.to_vp_array(), u_time: self.time_from_start, },
-            &params
-        ).unwrap();
-
-        target.finish().unwrap();
-    }
-
-    fn process_events(&mut self) -> Action {
-        let recalc_cam_pos = |cam: &mut camera::PerspectiveCamera, angle: f64, r: f32| {
-                let new_pos = Vec3::new(angle.to_radians().sin() as f32, 0.0, angle.to_radians().cos() as f32) * r;
-                cam.with_position_mut(new_pos)
-                    .with_rotation_mut(Vec3::new(0.0, -angle.to_radians() as f32, 0.0));
-        };
-
-        for event in self.display.poll_events() {
-            match event {
-                Event::Closed => return Action::Stop,
-
-                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Add)) => {
-                    self.state.step_forward();
-                }
-
-                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Up)) => {
-                    self.r -= 7.5;
-                    recalc_cam_pos(&mut self.camera, self.angle, self.r);
-                },
-
-                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Down)) => {
-                    self.r += 7.5;
-                    recalc_cam_pos(&mut self.camera, self.angle, self.r);
-                },
-
-                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::A)) => {
-                    self.camera.add_position(Vec3::new(-1.0, 0.0, 0.0));
-                },
-
-                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::D)) => {
-                    self.camera.add_position(Vec3::new(1.0, 0.0, 0.0));
-                },
-
-                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::W)) => {
-                    self.camera.add_position(Vec3::new(0.0, -1.0, 0.0));
-                },
-
-                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::S)) => {
-                    self.camera.add_position(Vec3::new(0.0, 1.0, 0.0));
-                },
-
-                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Left)) => {
-                    self.angle -= 1.0;
-                    recalc_cam_pos(&mut self.camera, self.angle, self.r);
-                },
-
-                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Right)) => {
-                    self.angle += 1.0;
-                    recalc_cam_pos(&mut self.camera, self.angle, self.r);
-                },
-
-                Event::Resized(x, y) => {
-                    self.camera.with_view_dimensions_mut(x, y);
-                },
-                _ => {}
-            }
-        }
-        Action::Continue
-    }
+#[cfg(feature = "headless")]
+fn open_window(_: State, _: &cli::Options) -> Result<(), StartupError> {
+    Err(StartupError::Usage(String::from("this build has no window, run it with --batch N")))
 }
 
 fn main() {
-    let mut sterek = Applicaton::new(State::new((50, 50, 50)));
-    sterek.main_loop();
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        std::process::exit(match e {
+            StartupError::Usage(_) => 2,
+            _ => 1,
+        });
+    }
 }
@@ -0,0 +1,337 @@
+//! Surface meshes of the live cells, for 3D printing and rendering in other
+//! tools. Every live cell is a unit cube with its lowest corner at the
+//! cell's coordinates; only faces between a live and a dead cell are kept,
+//! so the mesh is closed. Vertices are shared between faces.
+//!
+//! Merged meshes cover the same surface with fewer, larger faces. Where a
+//! corner of another face lies on the edge of a merged one, the edge is
+//! split there, so merged faces may have more than four corners and the
+//! mesh stays watertight.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use world::{World, I3d};
+
+pub struct Mesh {
+    pub vertices: Vec<I3d>,
+    /// Vertices of every face, counter-clockwise seen from outside and
+    /// starting at a corner.
+    pub faces: Vec<Vec<u32>>,
+}
+
+/// Exposed faces lying in one plane, all facing the same way.
+struct Plane {
+    /// Axis the faces are perpendicular to.
+    axis: usize,
+    /// True if they face towards increasing coordinates.
+    positive: bool,
+    /// Coordinate of the plane along `axis`.
+    at: i64,
+    /// Cells behind the faces, by their coordinates along the next two
+    /// axes in cyclic order.
+    cells: HashSet<(i64, i64)>,
+}
+
+fn coord(p: I3d, axis: usize) -> i64 {
+    match axis {
+        0 => p.0,
+        1 => p.1,
+        _ => p.2,
+    }
+}
+
+/// Point with coordinate `at` along `axis` and `u`, `v` along the next two.
+fn point(axis: usize, at: i64, u: i64, v: i64) -> I3d {
+    match axis {
+        0 => (at, u, v),
+        1 => (v, at, u),
+        _ => (u, v, at),
+    }
+}
+
+/// Splits a plane's faces into rectangles `(u, v, width, height)`, each as
+/// wide as possible and then as tall as possible.
+fn merge(cells: &HashSet<(i64, i64)>) -> Vec<(i64, i64, i64, i64)> {
+    let mut sorted: Vec<_> = cells.iter().map(|&(u, v)| (v, u)).collect();
+    sorted.sort();
+    let mut done = HashSet::new();
+    let mut rects = Vec::new();
+    for (v, u) in sorted {
+        if done.contains(&(u, v)) {
+            continue;
+        }
+        let free = |u: i64, v: i64| cells.contains(&(u, v)) && !done.contains(&(u, v));
+        let mut width = 1;
+        while free(u + width, v) {
+            width += 1;
+        }
+        let mut height = 1;
+        while (u..u + width).all(|u| free(u, v + height)) {
+            height += 1;
+        }
+        for du in 0..width {
+            for dv in 0..height {
+                done.insert((u + du, v + dv));
+            }
+        }
+        rects.push((u, v, width, height));
+    }
+    rects
+}
+
+impl Mesh {
+    /// Builds the surface of every live cell of `world`, merging coplanar
+    /// faces into larger ones if `merge_faces` is set.
+    pub fn from_world<W: World + ?Sized>(world: &W, merge_faces: bool) -> Mesh {
+        let cells: HashSet<I3d> = world.live_cells().collect();
+        let mut planes: HashMap<(usize, bool, i64), Plane> = HashMap::new();
+        for &p in cells.iter() {
+            for axis in 0..3 {
+                for &positive in [false, true].iter() {
+                    let step = if positive { 1 } else { -1 };
+                    let neighbour = match axis {
+                        0 => (p.0 + step, p.1, p.2),
+                        1 => (p.0, p.1 + step, p.2),
+                        _ => (p.0, p.1, p.2 + step),
+                    };
+                    if cells.contains(&neighbour) {
+                        continue;
+                    }
+                    let at = coord(p, axis) + if positive { 1 } else { 0 };
+                    planes.entry((axis, positive, at)).or_insert_with(|| Plane {
+                        axis: axis,
+                        positive: positive,
+                        at: at,
+                        cells: HashSet::new(),
+                    }).cells.insert((coord(p, (axis + 1) % 3), coord(p, (axis + 2) % 3)));
+                }
+            }
+        }
+
+        // Planes in a fixed order, so equal worlds give identical files.
+        let mut planes: Vec<_> = planes.into_iter().map(|(_, plane)| plane).collect();
+        planes.sort_by_key(|p| (p.axis, p.at, p.positive));
+        let mut rects = Vec::new();
+        for plane in planes {
+            let plane_rects = if merge_faces {
+                merge(&plane.cells)
+            } else {
+                let mut cells: Vec<_> = plane.cells.iter().map(|&(u, v)| (v, u)).collect();
+                cells.sort();
+                cells.into_iter().map(|(v, u)| (u, v, 1, 1)).collect()
+            };
+            for (u, v, w, h) in plane_rects {
+                // Going round u, then v is counter-clockwise seen from the
+                // positive side of the plane.
+                let mut corners = [(u, v), (u + w, v), (u + w, v + h), (u, v + h)];
+                if !plane.positive {
+                    corners.reverse();
+                }
+                let mut rect = [(0, 0, 0); 4];
+                for (r, &(cu, cv)) in rect.iter_mut().zip(corners.iter()) {
+                    *r = point(plane.axis, plane.at, cu, cv);
+                }
+                rects.push(rect);
+            }
+        }
+
+        // Every corner, by the line along each axis it lies on.
+        let mut lines: HashMap<(usize, i64, i64), Vec<i64>> = HashMap::new();
+        for &corner in rects.iter().flat_map(|r| r.iter()) {
+            for axis in 0..3 {
+                let line = (axis, coord(corner, (axis + 1) % 3), coord(corner, (axis + 2) % 3));
+                lines.entry(line).or_insert_with(Vec::new).push(coord(corner, axis));
+            }
+        }
+        for line in lines.values_mut() {
+            line.sort();
+            line.dedup();
+        }
+
+        let mut mesh = Mesh { vertices: Vec::new(), faces: Vec::new() };
+        let mut indices = HashMap::new();
+        for rect in rects {
+            let mut face = Vec::with_capacity(4);
+            for i in 0..4 {
+                let (a, b) = (rect[i], rect[(i + 1) % 4]);
+                let mut points = vec![a];
+                // Corners of other faces strictly inside the edge from a to b.
+                let axis = (0..3).find(|&axis| coord(a, axis) != coord(b, axis)).unwrap();
+                let (from, to) = (coord(a, axis), coord(b, axis));
+                let others = ((axis + 1) % 3, (axis + 2) % 3);
+                let line = &lines[&(axis, coord(a, others.0), coord(a, others.1))];
+                let start = match line.binary_search(&from.min(to)) {
+                    Ok(i) => i + 1,
+                    Err(i) => i,
+                };
+                let end = match line.binary_search(&from.max(to)) {
+                    Ok(i) | Err(i) => i,
+                };
+                let inside = line[start..end].iter().cloned();
+                let on_edge = |c: i64| match axis {
+                    0 => (c, a.1, a.2),
+                    1 => (a.0, c, a.2),
+                    _ => (a.0, a.1, c),
+                };
+                if from < to {
+                    points.extend(inside.map(on_edge));
+                } else {
+                    points.extend(inside.rev().map(on_edge));
+                }
+                for p in points {
+                    let vertices = &mut mesh.vertices;
+                    face.push(*indices.entry(p).or_insert_with(|| {
+                        vertices.push(p);
+                        vertices.len() as u32 - 1
+                    }));
+                }
+            }
+            mesh.faces.push(face);
+        }
+        mesh
+    }
+
+    /// Outward unit normal of a face.
+    pub fn normal(&self, face: &[u32]) -> I3d {
+        // The first vertex is a corner, so its neighbours lie along the two
+        // edges meeting there.
+        let (a, b, c) = (self.vertices[face[0] as usize], self.vertices[face[1] as usize],
+                         self.vertices[face[face.len() - 1] as usize]);
+        let (e, f) = ((b.0 - a.0, b.1 - a.1, b.2 - a.2), (c.0 - a.0, c.1 - a.1, c.2 - a.2));
+        let n = (e.1 * f.2 - e.2 * f.1, e.2 * f.0 - e.0 * f.2, e.0 * f.1 - e.1 * f.0);
+        (n.0.signum(), n.1.signum(), n.2.signum())
+    }
+
+    pub fn write_obj<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        try!(writeln!(writer, "# {} vertices, {} faces", self.vertices.len(), self.faces.len()));
+        for &(x, y, z) in self.vertices.iter() {
+            try!(writeln!(writer, "v {} {} {}", x, y, z));
+        }
+        for face in self.faces.iter() {
+            try!(write!(writer, "f"));
+            for &i in face.iter() {
+                try!(write!(writer, " {}", i + 1));
+            }
+            try!(writeln!(writer, ""));
+        }
+        Ok(())
+    }
+
+    /// Writes binary STL. Faces with four vertices become two triangles;
+    /// larger ones, whose extra vertices lie on their edges, become a fan
+    /// around their centre so that no triangle is degenerate.
+    pub fn write_stl<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let at = |i: u32| {
+            let (x, y, z) = self.vertices[i as usize];
+            [x as f32, y as f32, z as f32]
+        };
+        let mut triangles = Vec::new();
+        for face in self.faces.iter() {
+            let (nx, ny, nz) = self.normal(face);
+            let normal = [nx as f32, ny as f32, nz as f32];
+            if face.len() == 4 {
+                triangles.push([normal, at(face[0]), at(face[1]), at(face[2])]);
+                triangles.push([normal, at(face[0]), at(face[2]), at(face[3])]);
+            } else {
+                let mut centre = [0.0; 3];
+                for k in 0..3 {
+                    let c = face.iter().map(|&i| at(i)[k]);
+                    let (low, high) = c.fold((::std::f32::MAX, ::std::f32::MIN), |(l, h), c| (l.min(c), h.max(c)));
+                    centre[k] = (low + high) / 2.0;
+                }
+                for (k, &i) in face.iter().enumerate() {
+                    triangles.push([normal, at(i), at(face[(k + 1) % face.len()]), centre]);
+                }
+            }
+        }
+
+        let mut header = [b' '; 80];
+        let title = b"game_of_life surface mesh";
+        header[..title.len()].copy_from_slice(title);
+        try!(writer.write_all(&header));
+        try!(write_u32(&mut writer, triangles.len() as u32));
+        for triangle in triangles.iter() {
+            for f in triangle.iter().flat_map(|v| v.iter()) {
+                try!(write_u32(&mut writer, f.to_bits()));
+            }
+            // Attribute byte count, unused.
+            try!(writer.write_all(&[0, 0]));
+        }
+        Ok(())
+    }
+
+    /// Writes ASCII PLY with polygon faces.
+    pub fn write_ply<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let count = if self.faces.iter().all(|f| f.len() <= 255) { "uchar" } else { "uint" };
+        try!(write!(writer, "ply\nformat ascii 1.0\n\
+                             element vertex {}\nproperty int x\nproperty int y\nproperty int z\n\
+                             element face {}\nproperty list {} int vertex_indices\nend_header\n",
+                    self.vertices.len(), self.faces.len(), count));
+        for &(x, y, z) in self.vertices.iter() {
+            try!(writeln!(writer, "{} {} {}", x, y, z));
+        }
+        for face in self.faces.iter() {
+            try!(write!(writer, "{}", face.len()));
+            for &i in face.iter() {
+                try!(write!(writer, " {}", i));
+            }
+            try!(writeln!(writer, ""));
+        }
+        Ok(())
+    }
+
+    pub fn write<W: Write>(&self, format: Format, writer: W) -> io::Result<()> {
+        match format {
+            Format::Obj => self.write_obj(writer),
+            Format::Stl => self.write_stl(writer),
+            Format::Ply => self.write_ply(writer),
+        }
+    }
+}
+
+fn write_u32<W: Write>(writer: &mut W, v: u32) -> io::Result<()> {
+    writer.write_all(&[v as u8, (v >> 8) as u8, (v >> 16) as u8, (v >> 24) as u8])
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// Wavefront OBJ.
+    Obj,
+    /// Binary STL.
+    Stl,
+    /// ASCII PLY.
+    Ply,
+}
+
+impl Format {
+    /// Picks the format from a file's extension.
+    pub fn from_path(path: &str) -> Option<Format> {
+        path.rsplit('.').next().and_then(|ext| ext.to_lowercase().parse().ok())
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Format::Obj => "obj",
+            Format::Stl => "stl",
+            Format::Ply => "ply",
+        })
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "obj" => Ok(Format::Obj),
+            "stl" => Ok(Format::Stl),
+            "ply" => Ok(Format::Ply),
+            _ => Err(format!("unknown mesh format: {}, expected obj, stl or ply", s)),
+        }
+    }
+}
@@ -1,7 +1,7 @@
 extern crate glium;
 extern crate std;
 
-use glium::vertex::{Vertex, VertexBufferAny, PerInstance};
+use glium::vertex::{Vertex, VertexBufferAny, PerInstance, BufferCreationError};
 use glium::index::NoIndices;
 use glium::VertexBuffer;
 use glium::backend::Facade;
@@ -22,12 +22,12 @@ impl<T> InstancedObjects<T>
         facade: &F,
         vertex_info: (VertexBufferAny, NoIndices),
         per_instance_data: Vec<T>
-    ) -> InstancedObjects<T> {
-        InstancedObjects {
+    ) -> Result<InstancedObjects<T>, BufferCreationError> {
+        Ok(InstancedObjects {
             vertices: vertex_info.0,
             indices: vertex_info.1,
-            per_instance: VertexBuffer::dynamic(facade, &*per_instance_data.into_boxed_slice()).unwrap()
-        }
+            per_instance: try!(VertexBuffer::dynamic(facade, &*per_instance_data.into_boxed_slice()))
+        })
     }
 
     pub fn get_vertices_data(&self) -> (&VertexBufferAny, PerInstance) {
@@ -0,0 +1,174 @@
+//! Bit-packed rows: 64 cells per `u64` along z, neighbours counted 64 at a
+//! time with bit-sliced adders.
+
+use boundary::Boundary;
+use rule::Rule;
+
+pub const WORD_BITS: usize = 64;
+
+/// Bits needed to count up to 26 neighbours.
+const COUNTER_BITS: usize = 5;
+
+/// One counter bit per plane, 64 lanes wide.
+pub type Counter = [u64; COUNTER_BITS];
+
+#[inline]
+pub fn words_per_row(zs: usize) -> usize {
+    (zs + WORD_BITS - 1) / WORD_BITS
+}
+
+/// Mask of the bits in use in the last word of a row.
+#[inline]
+pub fn tail_mask(zs: usize) -> u64 {
+    match zs % WORD_BITS {
+        0 => !0,
+        r => (1 << r) - 1,
+    }
+}
+
+#[inline]
+pub fn get_bit(row: &[u64], z: usize) -> bool {
+    row[z / WORD_BITS] >> (z % WORD_BITS) & 1 != 0
+}
+
+#[inline]
+pub fn set_bit(row: &mut [u64], z: usize, alive: bool) {
+    let bit = 1 << (z % WORD_BITS);
+    if alive {
+        row[z / WORD_BITS] |= bit;
+    } else {
+        row[z / WORD_BITS] &= !bit;
+    }
+}
+
+/// Scrambles a word (the splitmix64 finaliser), for hashing cells.
+#[inline]
+pub fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// A neighbouring row of the previous generation.
+#[derive(Copy, Clone)]
+pub enum Row<'a> {
+    Cells(&'a [u64]),
+    /// A row beyond an x or y edge, every cell in the given state.
+    Wall(bool),
+}
+
+impl<'a> Row<'a> {
+    #[inline]
+    fn word(&self, w: usize, last: usize, tail: u64) -> u64 {
+        match *self {
+            Row::Cells(r) => r[w],
+            Row::Wall(false) => 0,
+            Row::Wall(true) => if w == last { tail } else { !0 },
+        }
+    }
+
+    /// States of the cells at z = -1 and z = zs.
+    fn z_edges(&self, zs: usize, bz: Boundary) -> (bool, bool) {
+        match (*self, bz) {
+            (_, Boundary::Dead) => (false, false),
+            (Row::Wall(v), _) => (v, v),
+            (Row::Cells(_), Boundary::Alive) => (true, true),
+            (Row::Cells(r), Boundary::Periodic) => (get_bit(r, zs - 1), get_bit(r, 0)),
+            (Row::Cells(r), Boundary::Mirror) => (get_bit(r, 0), get_bit(r, zs - 1)),
+        }
+    }
+}
+
+/// Adds a one-bit-per-lane value to a bit-sliced counter.
+#[inline]
+fn add(counter: &mut Counter, mut carry: u64) {
+    for plane in counter.iter_mut() {
+        let next = *plane & carry;
+        *plane ^= carry;
+        carry = next;
+        if carry == 0 {
+            break;
+        }
+    }
+}
+
+/// Applies `rule` to 64 cells at once.
+#[inline]
+fn apply_rule(rule: &Rule, alive: u64, counter: &Counter) -> u64 {
+    let (birth, stay) = (rule.birth_mask(), rule.stay_mask());
+    let (mut born, mut stays) = (0, 0);
+    let mut counts = birth | stay;
+    while counts != 0 {
+        let n = counts.trailing_zeros();
+        counts &= counts - 1;
+        let mut eq = !0;
+        for (k, plane) in counter.iter().enumerate() {
+            eq &= if n >> k & 1 != 0 { *plane } else { !*plane };
+        }
+        if birth >> n & 1 != 0 {
+            born |= eq;
+        }
+        if stay >> n & 1 != 0 {
+            stays |= eq;
+        }
+    }
+    (!alive & born) | (alive & stays)
+}
+
+/// Computes the next generation of a single-word row of an unbounded world.
+///
+/// Each entry of `rows` is a neighbouring row in the x/y plane given as its
+/// word and the states of the cells just below bit 0 and just above bit 63;
+/// the entry at `centre` is the cell's own row.
+pub fn step_word(rows: &[(u64, bool, bool)], centre: usize, rule: &Rule) -> u64 {
+    let mut counter = [0; COUNTER_BITS];
+    for (i, &(word, below, above)) in rows.iter().enumerate() {
+        if i != centre {
+            add(&mut counter, word);
+        }
+        add(&mut counter, word << 1 | below as u64);
+        add(&mut counter, word >> 1 | (above as u64) << 63);
+    }
+    apply_rule(rule, rows[centre].0, &counter)
+}
+
+/// Computes the next generation of one row.
+///
+/// `rows` are the (up to nine) neighbouring rows in the x/y plane, the row
+/// at `centre` being the cell's own. `counters` is scratch space.
+pub fn step_row(rows: &[Row], centre: usize, zs: usize, bz: Boundary, rule: &Rule,
+                counters: &mut Vec<Counter>, out: &mut [u64]) {
+    let wpr = words_per_row(zs);
+    let last = wpr - 1;
+    let tail = tail_mask(zs);
+    counters.clear();
+    counters.resize(wpr, [0; COUNTER_BITS]);
+
+    for (i, row) in rows.iter().enumerate() {
+        let (low, high) = if zs > 1 { row.z_edges(zs, bz) } else { (false, false) };
+        for w in 0..wpr {
+            let word = row.word(w, last, tail);
+            let counter = &mut counters[w];
+            if i != centre {
+                add(counter, word);
+            }
+            if zs > 1 {
+                let below = if w > 0 { row.word(w - 1, last, tail) >> 63 } else { low as u64 };
+                add(counter, word << 1 | below);
+                let mut above = word >> 1;
+                if w < last {
+                    above |= row.word(w + 1, last, tail) << 63;
+                } else {
+                    above |= (high as u64) << ((zs - 1) % WORD_BITS);
+                }
+                add(counter, above);
+            }
+        }
+    }
+
+    let own = rows[centre];
+    for w in 0..wpr {
+        out[w] = apply_rule(rule, own.word(w, last, tail), &counters[w]);
+    }
+    out[last] &= tail;
+}
@@ -0,0 +1,146 @@
+//! Built-in library of small, well-known patterns, each tagged with the
+//! rule it lives under.
+
+use rule::Rule;
+use state::{State, U3d};
+
+pub struct Pattern {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Rule the pattern behaves as described under.
+    pub rule: &'static str,
+    /// Generations until the pattern repeats, 1 for still lifes.
+    pub period: u32,
+    /// How far the pattern moves every period; zero unless it is a
+    /// spaceship.
+    pub displacement: (i32, i32, i32),
+    /// Live cells, relative to the corner of the bounding box.
+    pub cells: &'static [(u8, u8, u8)],
+}
+
+impl Pattern {
+    pub fn rule(&self) -> Rule {
+        Rule::parse(self.rule).unwrap()
+    }
+
+    /// Size of the bounding box.
+    pub fn size(&self) -> U3d {
+        self.cells.iter().fold((0, 0, 0), |(xs, ys, zs), &(x, y, z)| (
+            xs.max(x as usize + 1),
+            ys.max(y as usize + 1),
+            zs.max(z as usize + 1),
+        ))
+    }
+
+    pub fn is_spaceship(&self) -> bool {
+        self.displacement != (0, 0, 0)
+    }
+
+    /// Sets the pattern's cells alive with its corner at `at`. Fails without
+    /// touching `state` if the pattern does not fit.
+    pub fn stamp(&self, state: &mut State, (x, y, z): U3d) -> Result<(), String> {
+        let (xs, ys, zs) = state.dim();
+        let (px, py, pz) = self.size();
+        if x + px > xs || y + py > ys || z + pz > zs {
+            return Err(format!("{} ({}x{}x{}) does not fit at {},{},{} in a {}x{}x{} world",
+                               self.name, px, py, pz, x, y, z, xs, ys, zs));
+        }
+        for &(cx, cy, cz) in self.cells {
+            state.set((x + cx as usize, y + cy as usize, z + cz as usize), true);
+        }
+        Ok(())
+    }
+}
+
+pub static PATTERNS: &'static [Pattern] = &[
+    Pattern {
+        name: "glider-4555",
+        description: "Bays' glider, moving diagonally in the x/z plane",
+        rule: "B5/S45",
+        period: 4,
+        displacement: (-1, 0, -1),
+        cells: &[(0, 1, 0), (0, 1, 1), (0, 2, 0), (0, 2, 1), (1, 0, 0),
+                 (1, 0, 1), (1, 3, 0), (1, 3, 1), (2, 1, 0), (2, 2, 0)],
+    },
+    Pattern {
+        name: "tub-4555",
+        description: "two stacked rings of four cells",
+        rule: "B5/S45",
+        period: 1,
+        displacement: (0, 0, 0),
+        cells: &[(0, 1, 0), (0, 1, 1), (1, 0, 0), (1, 0, 1),
+                 (1, 2, 0), (1, 2, 1), (2, 1, 0), (2, 1, 1)],
+    },
+    Pattern {
+        name: "boat-4555",
+        description: "the Life boat in two layers, an oscillator here",
+        rule: "B5/S45",
+        period: 4,
+        displacement: (0, 0, 0),
+        cells: &[(0, 0, 0), (0, 0, 1), (0, 1, 0), (0, 1, 1), (1, 0, 0),
+                 (1, 0, 1), (1, 2, 0), (1, 2, 1), (2, 1, 0), (2, 1, 1)],
+    },
+    Pattern {
+        name: "glider-5766",
+        description: "Bays' glider: the Life glider in two layers",
+        rule: "B6/S567",
+        period: 4,
+        displacement: (-1, -1, 0),
+        cells: &[(0, 0, 0), (0, 0, 1), (0, 1, 0), (0, 1, 1), (0, 2, 0),
+                 (0, 2, 1), (1, 0, 0), (1, 0, 1), (2, 1, 0), (2, 1, 1)],
+    },
+    Pattern {
+        name: "block-5766",
+        description: "2x2x2 cube",
+        rule: "B6/S567",
+        period: 1,
+        displacement: (0, 0, 0),
+        cells: &[(0, 0, 0), (0, 0, 1), (0, 1, 0), (0, 1, 1),
+                 (1, 0, 0), (1, 0, 1), (1, 1, 0), (1, 1, 1)],
+    },
+    Pattern {
+        name: "blinker-5766",
+        description: "the Life blinker in two layers",
+        rule: "B6/S567",
+        period: 2,
+        displacement: (0, 0, 0),
+        cells: &[(0, 0, 0), (0, 0, 1), (0, 1, 0), (0, 1, 1), (0, 2, 0), (0, 2, 1)],
+    },
+    Pattern {
+        name: "boat-5766",
+        description: "the Life boat in two layers",
+        rule: "B6/S567",
+        period: 1,
+        displacement: (0, 0, 0),
+        cells: &[(0, 0, 0), (0, 0, 1), (0, 1, 0), (0, 1, 1), (1, 0, 0),
+                 (1, 0, 1), (1, 2, 0), (1, 2, 1), (2, 1, 0), (2, 1, 1)],
+    },
+    Pattern {
+        name: "glider-life",
+        description: "Conway's glider, for a world one cell thick",
+        rule: "B3/S23",
+        period: 4,
+        displacement: (1, 1, 0),
+        cells: &[(1, 0, 0), (2, 1, 0), (0, 2, 0), (1, 2, 0), (2, 2, 0)],
+    },
+    Pattern {
+        name: "block-life",
+        description: "Conway's block, for a world one cell thick",
+        rule: "B3/S23",
+        period: 1,
+        displacement: (0, 0, 0),
+        cells: &[(0, 0, 0), (0, 1, 0), (1, 0, 0), (1, 1, 0)],
+    },
+    Pattern {
+        name: "blinker-life",
+        description: "Conway's blinker, for a world one cell thick",
+        rule: "B3/S23",
+        period: 2,
+        displacement: (0, 0, 0),
+        cells: &[(0, 0, 0), (0, 1, 0), (0, 2, 0)],
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Pattern> {
+    PATTERNS.iter().find(|p| p.name == name)
+}
@@ -0,0 +1,326 @@
+//! Run-length encoded patterns in the format of Golly's 3D.lua:
+//!
+//! ```text
+//! 3D version=1 size=30 pos=13,13,14 gen=0
+//! #C comment
+//! x=3 y=4 z=2 rule=3D4,5/5
+//! bo$obo$obo$bo/bo$obo$obo$bo!
+//! ```
+//!
+//! Cells run along x, `$` ends a row of increasing y and `/` a layer of
+//! increasing z. The `3D` line is optional; its `pos` is where the pattern's
+//! corner lies in the world.
+
+use std::io::{self, BufRead, Write};
+
+use rule::Rule;
+use state::{State, U3d};
+use world::{World, I3d};
+
+/// Lines of the body are wrapped at this width when writing.
+const LINE_WIDTH: usize = 70;
+
+/// Contents of an RLE file.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Rle {
+    pub comments: Vec<String>,
+    pub rule: Option<Rule>,
+    pub generation: u64,
+    /// World corner of the pattern's bounding box, if given.
+    pub position: Option<I3d>,
+    /// Size of the bounding box given by the `x`, `y` and `z` fields.
+    pub size: U3d,
+    /// Live cells relative to the bounding box.
+    pub cells: Vec<U3d>,
+}
+
+fn invalid_data(line: usize, msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, msg))
+}
+
+/// Splits `key=value` pairs separated by spaces or commas; spaces around
+/// `=` are allowed.
+fn fields(s: &str) -> Vec<(String, String)> {
+    let spaced = s.replace('=', " = ").replace(',', " ");
+    let tokens: Vec<_> = spaced.split_whitespace().collect();
+    let mut fields = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if i + 2 < tokens.len() && tokens[i + 1] == "=" {
+            fields.push((tokens[i].to_string(), tokens[i + 2].to_string()));
+            i += 3;
+        } else {
+            fields.push((tokens[i].to_string(), String::new()));
+            i += 1;
+        }
+    }
+    fields
+}
+
+fn parse_field<T: ::std::str::FromStr>(line: usize, key: &str, value: &str) -> io::Result<T> {
+    value.parse().map_err(|_| invalid_data(line, format!("invalid {}: {:?}", key, value)))
+}
+
+/// Parses the `3D version=1 size=N pos=X,Y,Z gen=G` line.
+fn read_3d_line(n: usize, line: &str, rle: &mut Rle) -> io::Result<()> {
+    for field in line[2..].split_whitespace() {
+        let mut parts = field.splitn(2, '=');
+        let (key, value) = (parts.next().unwrap(), parts.next().unwrap_or(""));
+        match key {
+            "version" if value != "1" =>
+                return Err(invalid_data(n, format!("unsupported version {}", value))),
+            "pos" => {
+                let c = try!(value.split(',').map(|c| parse_field::<i64>(n, "pos", c)).collect::<io::Result<Vec<_>>>());
+                if c.len() != 3 {
+                    return Err(invalid_data(n, format!("invalid pos: {:?}", value)));
+                }
+                rle.position = Some((c[0], c[1], c[2]));
+            },
+            "gen" => rle.generation = try!(parse_field(n, "gen", value)),
+            _ => {},
+        }
+    }
+    Ok(())
+}
+
+/// Parses the `x=.. y=.. z=.. rule=..` header.
+fn read_header(n: usize, line: &str, rle: &mut Rle) -> io::Result<()> {
+    // The rule may contain commas, so it is split off first.
+    let (dims, rule) = match line.find("rule") {
+        Some(i) => (&line[..i], Some(&line[i + 4..])),
+        None => (line, None),
+    };
+    let (mut x, mut y, mut z) = (None, None, None);
+    for (key, value) in fields(dims) {
+        match &key[..] {
+            "x" => x = Some(try!(parse_field(n, "x", &value))),
+            "y" => y = Some(try!(parse_field(n, "y", &value))),
+            "z" => z = Some(try!(parse_field(n, "z", &value))),
+            _ => return Err(invalid_data(n, format!("unexpected {:?} in header", key))),
+        }
+    }
+    rle.size = match (x, y, z) {
+        (Some(x), Some(y), Some(z)) => (x, y, z),
+        _ => return Err(invalid_data(n, String::from("header needs x, y and z"))),
+    };
+    if let Some(rule) = rule {
+        let rule = rule.trim_left();
+        if !rule.starts_with('=') {
+            return Err(invalid_data(n, String::from("expected = after rule")));
+        }
+        let rule = rule[1..].trim();
+        rle.rule = Some(try!(Rule::parse(rule).map_err(|e| invalid_data(n, format!("{}: {}", rule, e)))));
+    }
+    Ok(())
+}
+
+/// Reads an RLE file.
+pub fn read<R: BufRead>(reader: R) -> io::Result<Rle> {
+    let mut rle = Rle {
+        comments: Vec::new(),
+        rule: None,
+        generation: 0,
+        position: None,
+        size: (0, 0, 0),
+        cells: Vec::new(),
+    };
+    let mut header = false;
+    let mut done = false;
+    let (mut x, mut y, mut z) = (0, 0, 0);
+    let mut count = None;
+    for (n, line) in reader.lines().enumerate() {
+        let (n, line) = (n + 1, try!(line));
+        let line = line.trim();
+        if done || line.is_empty() {
+            continue;
+        }
+        if line.starts_with('#') {
+            let text = if line.starts_with("#C") || line.starts_with("#c") { &line[2..] } else { &line[1..] };
+            rle.comments.push(text.trim().to_string());
+            continue;
+        }
+        if !header {
+            if line == "3D" || line.starts_with("3D ") {
+                try!(read_3d_line(n, line, &mut rle));
+            } else if line.starts_with('x') {
+                try!(read_header(n, line, &mut rle));
+                header = true;
+            } else {
+                return Err(invalid_data(n, String::from("expected an x = .. y = .. z = .. header")));
+            }
+            continue;
+        }
+
+        let advance = |c: usize, run: usize| c.checked_add(run)
+            .ok_or_else(|| invalid_data(n, String::from("run reaches beyond any pattern")));
+        for c in line.chars() {
+            if let Some(d) = c.to_digit(10) {
+                let more = count.unwrap_or(0usize).checked_mul(10).and_then(|r| r.checked_add(d as usize));
+                count = Some(try!(more.ok_or_else(|| invalid_data(n, String::from("run count is too large")))));
+                continue;
+            }
+            if c.is_whitespace() {
+                continue;
+            }
+            let run = count.take().unwrap_or(1);
+            match c {
+                'b' | '.' => x = try!(advance(x, run)),
+                'o' => {
+                    let (xs, ys, zs) = rle.size;
+                    let end = try!(advance(x, run));
+                    if end > xs || y >= ys || z >= zs {
+                        return Err(invalid_data(n, format!("cells beyond the {}x{}x{} bounding box", xs, ys, zs)));
+                    }
+                    for i in 0..run {
+                        rle.cells.push((x + i, y, z));
+                    }
+                    x = end;
+                },
+                '$' => {
+                    x = 0;
+                    y = try!(advance(y, run));
+                },
+                '/' => {
+                    x = 0;
+                    y = 0;
+                    z = try!(advance(z, run));
+                },
+                '!' => {
+                    done = true;
+                    break;
+                },
+                c => return Err(invalid_data(n, format!("unexpected {:?}", c))),
+            }
+        }
+    }
+    if !header {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "missing x = .. y = .. z = .. header"));
+    }
+    Ok(rle)
+}
+
+/// Collects body items into lines of at most `LINE_WIDTH` characters.
+struct Body<W: Write> {
+    writer: W,
+    line: String,
+}
+
+impl<W: Write> Body<W> {
+    fn push(&mut self, count: usize, c: char) -> io::Result<()> {
+        let item = if count == 1 { c.to_string() } else { format!("{}{}", count, c) };
+        if self.line.len() + item.len() > LINE_WIDTH {
+            try!(writeln!(self.writer, "{}", self.line));
+            self.line.clear();
+        }
+        self.line.push_str(&item);
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        try!(self.push(1, '!'));
+        writeln!(self.writer, "{}", self.line)
+    }
+}
+
+/// Writes every live cell of `state`, with its rule, generation and
+/// `comments`.
+pub fn write<W: Write>(state: &State, comments: &[String], writer: W) -> io::Result<()> {
+    let (xs, ys, zs) = state.dim();
+    write_world(state, Some(xs.max(ys).max(zs)), comments, writer)
+}
+
+/// Writes every live cell of any world. `grid_size` is the size of the
+/// cubic grid of a bounded world, whose cells lie at their own coordinates
+/// in it; without one, the pattern goes at the corner of a grid just large
+/// enough to hold it.
+pub fn write_world<V: World + ?Sized, W: Write>(world: &V, grid_size: Option<usize>, comments: &[String],
+                                                mut writer: W) -> io::Result<()> {
+    let mut cells: Vec<I3d> = world.live_cells().map(|(x, y, z)| (z, y, x)).collect();
+    cells.sort();
+    let low = cells.iter().fold(None, |low: Option<I3d>, &(z, y, x)| Some(match low {
+        None => (x, y, z),
+        Some((lx, ly, lz)) => (lx.min(x), ly.min(y), lz.min(z)),
+    }));
+    let (lx, ly, lz) = low.unwrap_or((0, 0, 0));
+    let high = cells.iter().fold((lx - 1, ly - 1, lz - 1), |(hx, hy, hz), &(z, y, x)| {
+        (hx.max(x), hy.max(y), hz.max(z))
+    });
+
+    let (size, (px, py, pz)) = match grid_size {
+        Some(size) => (size, (lx, ly, lz)),
+        None => (((high.0 - lx).max(high.1 - ly).max(high.2 - lz) + 1).max(1) as usize, (0, 0, 0)),
+    };
+
+    try!(write!(writer, "3D version=1 size={} pos={},{},{}", size, px, py, pz));
+    if world.generation() > 0 {
+        try!(write!(writer, " gen={}", world.generation()));
+    }
+    try!(writeln!(writer, ""));
+    for comment in comments {
+        try!(writeln!(writer, "#C {}", comment));
+    }
+    try!(writeln!(writer, "x={} y={} z={} rule={}",
+                  high.0 - lx + 1, high.1 - ly + 1, high.2 - lz + 1, world.rule().to_golly()));
+
+    let mut body = Body { writer: writer, line: String::new() };
+    // Where the next item starts, and live cells not yet written.
+    let (mut x, mut y, mut z) = (0, 0, 0);
+    let mut run = 0;
+    for (cz, cy, cx) in cells.into_iter().map(|(z, y, x)| (z - lz, y - ly, x - lx)) {
+        if (cz, cy, cx) != (z, y, x) && run > 0 {
+            try!(body.push(run, 'o'));
+            run = 0;
+        }
+        if cz > z {
+            try!(body.push((cz - z) as usize, '/'));
+            z = cz;
+            y = 0;
+            x = 0;
+        }
+        if cy > y {
+            try!(body.push((cy - y) as usize, '$'));
+            y = cy;
+            x = 0;
+        }
+        if cx > x {
+            try!(body.push((cx - x) as usize, 'b'));
+        }
+        x = cx + 1;
+        run += 1;
+    }
+    if run > 0 {
+        try!(body.push(run, 'o'));
+    }
+    body.finish()
+}
+
+/// Sets the pattern's cells alive at its `pos`, or centred if it has none,
+/// and takes over its rule and generation. Fails without touching `state`
+/// if the pattern does not fit or its rule cannot run there.
+pub fn load<R: BufRead>(state: &mut State, reader: R) -> io::Result<Rle> {
+    let rle = try!(read(reader));
+    let (xs, ys, zs) = state.dim();
+    let (px, py, pz) = rle.size;
+    let (cx, cy, cz) = rle.position.unwrap_or((
+        (xs as i64 - px as i64) / 2,
+        (ys as i64 - py as i64) / 2,
+        (zs as i64 - pz as i64) / 2,
+    ));
+    let fits = |c: i64, p: usize, s: usize| c >= 0 && c as u64 + p as u64 <= s as u64;
+    if !(fits(cx, px, xs) && fits(cy, py, ys) && fits(cz, pz, zs)) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!(
+            "the {}x{}x{} pattern at {},{},{} does not fit in the {}x{}x{} world",
+            px, py, pz, cx, cy, cz, xs, ys, zs)));
+    }
+    if let Some(ref rule) = rle.rule {
+        try!(state.check_rule(rule).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)));
+    }
+    for &(x, y, z) in rle.cells.iter() {
+        state.set((cx as usize + x, cy as usize + y, cz as usize + z), true);
+    }
+    if let Some(rule) = rle.rule {
+        state.set_rule(rule);
+    }
+    state.set_generation(rle.generation);
+    Ok(rle)
+}
@@ -0,0 +1,351 @@
+//! Outer-totalistic birth/stay rules and their string notations.
+//!
+//! Accepted forms (case-insensitive, surrounding whitespace ignored):
+//!
+//! * `B5/S45` – birth and stay lists in either order. Ranges are written
+//!   `5-7` or `5..7`. Each digit is one count, so `B10` means births with 0
+//!   or 1 neighbours, unless the rule contains a `,`: then counts are
+//!   separated by commas and may be multi-digit (`B5,13/S4..12`).
+//! * `4555` – Bays' `ElEuFlFu` notation: a live cell stays alive with
+//!   `El..=Eu` neighbours, a dead one is born with `Fl..=Fu`.
+//! * `3D4,5/5` – Golly 3D.lua notation: stay list, `/`, birth list, with an
+//!   optional trailing `M` (Moore) neighbourhood letter.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Number of neighbours of a cell in the 3D Moore neighbourhood.
+pub const MOORE_NEIGHBOURS: u32 = 26;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Rule {
+    birth: u32,
+    stay: u32,
+}
+
+impl Rule {
+    /// Builds a rule from explicit neighbour counts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a count exceeds `MOORE_NEIGHBOURS`.
+    pub fn new(birth: &[u32], stay: &[u32]) -> Rule {
+        let mask = |counts: &[u32]| counts.iter().fold(0, |m, &n| {
+            assert!(n <= MOORE_NEIGHBOURS, "neighbour count {} is out of range", n);
+            m | 1 << n
+        });
+        Rule { birth: mask(birth), stay: mask(stay) }
+    }
+
+    pub fn parse(s: &str) -> Result<Rule, RuleParseError> {
+        Rule::parse_with_neighbourhood(s, MOORE_NEIGHBOURS)
+    }
+
+    /// Parses a rule for a neighbourhood of `neighbours` cells, e.g. 8 for a
+    /// 2D slab.
+    pub fn parse_with_neighbourhood(s: &str, neighbours: u32) -> Result<Rule, RuleParseError> {
+        Parser::new(s, neighbours).parse()
+    }
+
+    /// Highest neighbour count the rule refers to.
+    pub fn max_count(&self) -> Option<u32> {
+        (0..MOORE_NEIGHBOURS + 1).rev().find(|n| (self.birth | self.stay) & 1 << n != 0)
+    }
+
+    pub fn birth_counts(&self) -> Vec<u32> {
+        (0..MOORE_NEIGHBOURS + 1).filter(|n| self.birth & 1 << n != 0).collect()
+    }
+
+    pub fn stay_counts(&self) -> Vec<u32> {
+        (0..MOORE_NEIGHBOURS + 1).filter(|n| self.stay & 1 << n != 0).collect()
+    }
+
+    /// Bit `n` is set when a dead cell with `n` neighbours is born.
+    #[inline]
+    pub fn birth_mask(&self) -> u32 {
+        self.birth
+    }
+
+    /// Bit `n` is set when a live cell with `n` neighbours stays alive.
+    #[inline]
+    pub fn stay_mask(&self) -> u32 {
+        self.stay
+    }
+
+    /// State of a cell in the next generation.
+    #[inline]
+    pub fn next_state(&self, alive: bool, neighbours: u32) -> bool {
+        let mask = if alive { self.stay } else { self.birth };
+        mask >> neighbours & 1 != 0
+    }
+
+    /// The rule in the notation of Golly's 3D.lua, `3D<stay>/<birth>`.
+    pub fn to_golly(&self) -> String {
+        let join = |counts: Vec<u32>| counts.iter()
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        format!("3D{}/{}", join(self.stay_counts()), join(self.birth_counts()))
+    }
+}
+
+impl Default for Rule {
+    /// Bays' 4555.
+    fn default() -> Rule {
+        Rule::new(&[5], &[4, 5])
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let birth = self.birth_counts();
+        let stay = self.stay_counts();
+        let sep = if birth.iter().chain(stay.iter()).any(|&n| n > 9) { "," } else { "" };
+        if sep == "," && birth.len() <= 1 && stay.len() <= 1 {
+            // Without a comma `B13` would read back as B1 and B3.
+            return f.write_str(&self.to_golly());
+        }
+        let join = |counts: &[u32]| counts.iter()
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join(sep);
+        write!(f, "B{}/S{}", join(&birth), join(&stay))
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleParseError;
+
+    fn from_str(s: &str) -> Result<Rule, RuleParseError> {
+        Rule::parse(s)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RuleParseErrorKind {
+    Empty,
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    DuplicatePart(char),
+    /// The count and the neighbourhood size it exceeds.
+    CountOutOfRange(u32, u32),
+    EmptyRange(u32, u32),
+    UnsupportedNeighbourhood(char),
+}
+
+/// Error returned by `Rule::parse`. `position` is a byte offset into the
+/// original string.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RuleParseError {
+    pub kind: RuleParseErrorKind,
+    pub position: usize,
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::RuleParseErrorKind::*;
+        try!(write!(f, "invalid rule at column {}: ", self.position + 1));
+        match self.kind {
+            Empty => write!(f, "rule is empty"),
+            UnexpectedChar(c) => write!(f, "unexpected character '{}'", c),
+            UnexpectedEnd => write!(f, "unexpected end of rule"),
+            DuplicatePart(c) => write!(f, "'{}' list given twice", c),
+            CountOutOfRange(n, max) =>
+                write!(f, "neighbour count {} exceeds {}", n, max),
+            EmptyRange(lo, hi) => write!(f, "range {}..{} is empty", lo, hi),
+            UnsupportedNeighbourhood(c) =>
+                write!(f, "neighbourhood '{}' is not supported, only Moore (M)", c),
+        }
+    }
+}
+
+impl Error for RuleParseError {
+    fn description(&self) -> &str {
+        "invalid rule"
+    }
+}
+
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+    max: u32,
+}
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str, max: u32) -> Parser<'a> {
+        Parser { s: s.as_bytes(), pos: 0, max: max }
+    }
+
+    fn parse(mut self) -> Result<Rule, RuleParseError> {
+        let end = self.s.iter().rposition(|c| !c.is_ascii_whitespace()).map_or(0, |e| e + 1);
+        self.s = &self.s[..end];
+        while self.peek().map_or(false, |c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+
+        match self.peek() {
+            None => self.err(RuleParseErrorKind::Empty),
+            Some(b'3') if self.s.get(self.pos + 1).map(|c| c.to_ascii_uppercase()) == Some(b'D') => {
+                self.pos += 2;
+                self.golly()
+            },
+            Some(c) if c.is_ascii_digit() => self.bays(),
+            _ => self.birth_stay(),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).cloned()
+    }
+
+    fn err<T>(&self, kind: RuleParseErrorKind) -> Result<T, RuleParseError> {
+        Err(RuleParseError { kind: kind, position: self.pos })
+    }
+
+    fn unexpected<T>(&self) -> Result<T, RuleParseError> {
+        match self.peek() {
+            Some(c) => self.err(RuleParseErrorKind::UnexpectedChar(c as char)),
+            None => self.err(RuleParseErrorKind::UnexpectedEnd),
+        }
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek().map(|p| p.to_ascii_uppercase()) == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Reads one count; a single digit unless `multi_digit` is set.
+    fn count(&mut self, multi_digit: bool) -> Result<u32, RuleParseError> {
+        let start = self.pos;
+        let mut n = 0u32;
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_digit() || (!multi_digit && self.pos > start) {
+                break;
+            }
+            n = n.saturating_mul(10).saturating_add((c - b'0') as u32);
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return self.unexpected();
+        }
+        if n > self.max {
+            return Err(RuleParseError {
+                kind: RuleParseErrorKind::CountOutOfRange(n, self.max),
+                position: start,
+            });
+        }
+        Ok(n)
+    }
+
+    /// Reads a count or a `lo-hi` / `lo..hi` range and returns it as a mask.
+    fn item(&mut self, multi_digit: bool) -> Result<u32, RuleParseError> {
+        let start = self.pos;
+        let lo = try!(self.count(multi_digit));
+        let hi = if self.eat(b'-') {
+            try!(self.count(multi_digit))
+        } else if self.s[self.pos..].starts_with(b"..") {
+            self.pos += 2;
+            try!(self.count(multi_digit))
+        } else {
+            lo
+        };
+        if hi < lo {
+            return Err(RuleParseError {
+                kind: RuleParseErrorKind::EmptyRange(lo, hi),
+                position: start,
+            });
+        }
+        Ok((lo..hi + 1).fold(0, |m, n| m | 1 << n))
+    }
+
+    /// Reads a list of counts up to the next `/` or the end of input.
+    fn list(&mut self, multi_digit: bool) -> Result<u32, RuleParseError> {
+        let mut mask = 0;
+        loop {
+            match self.peek() {
+                None | Some(b'/') => return Ok(mask),
+                Some(c) if c.is_ascii_digit() => mask |= try!(self.item(multi_digit)),
+                Some(c) if c.is_ascii_alphabetic() && multi_digit => return Ok(mask),
+                _ => return self.unexpected(),
+            }
+            if multi_digit && !self.eat(b',') {
+                match self.peek() {
+                    None | Some(b'/') => return Ok(mask),
+                    Some(c) if c.is_ascii_alphabetic() => return Ok(mask),
+                    _ => return self.unexpected(),
+                }
+            }
+        }
+    }
+
+    fn birth_stay(&mut self) -> Result<Rule, RuleParseError> {
+        let multi_digit = self.s.contains(&b',');
+        let (mut birth, mut stay) = (None, None);
+        loop {
+            let part_pos = self.pos;
+            let letter = match self.peek().map(|c| c.to_ascii_uppercase()) {
+                Some(c @ b'B') | Some(c @ b'S') => c,
+                _ => return self.unexpected(),
+            };
+            self.pos += 1;
+            let mask = try!(self.list(multi_digit));
+            let slot = if letter == b'B' { &mut birth } else { &mut stay };
+            if slot.is_some() {
+                return Err(RuleParseError {
+                    kind: RuleParseErrorKind::DuplicatePart(letter as char),
+                    position: part_pos,
+                });
+            }
+            *slot = Some(mask);
+
+            if self.peek().is_none() {
+                break;
+            }
+            if !self.eat(b'/') {
+                return self.unexpected();
+            }
+        }
+        Ok(Rule { birth: birth.unwrap_or(0), stay: stay.unwrap_or(0) })
+    }
+
+    fn bays(&mut self) -> Result<Rule, RuleParseError> {
+        let mut r = [0; 4];
+        for slot in r.iter_mut() {
+            *slot = try!(self.count(false));
+        }
+        if self.peek().is_some() {
+            return self.unexpected();
+        }
+        let range = |lo: u32, hi: u32, pos: usize| if hi < lo {
+            Err(RuleParseError { kind: RuleParseErrorKind::EmptyRange(lo, hi), position: pos })
+        } else {
+            Ok((lo..hi + 1).fold(0, |m, n| m | 1 << n))
+        };
+        let start = self.pos - 4;
+        Ok(Rule {
+            stay: try!(range(r[0], r[1], start)),
+            birth: try!(range(r[2], r[3], start + 2)),
+        })
+    }
+
+    fn golly(&mut self) -> Result<Rule, RuleParseError> {
+        let stay = try!(self.list(true));
+        if !self.eat(b'/') {
+            return self.unexpected();
+        }
+        let birth = try!(self.list(true));
+        match self.peek().map(|c| c.to_ascii_uppercase()) {
+            None => {},
+            Some(b'M') if self.pos + 1 == self.s.len() => {},
+            Some(c) if c.is_ascii_alphabetic() && self.pos + 1 == self.s.len() =>
+                return self.err(RuleParseErrorKind::UnsupportedNeighbourhood(c as char)),
+            _ => return self.unexpected(),
+        }
+        Ok(Rule { birth: birth, stay: stay })
+    }
+}
@@ -0,0 +1,329 @@
+//! Control server speaking JSON lines over a Unix socket or a localhost TCP
+//! port. Every request is one object naming a `command`; every reply is one
+//! object with `"ok": true` and the results, or `"ok": false` and an
+//! `error`. An `id` given with a request is sent back with its reply.
+//!
+//! ```text
+//! {"command": "stats"}                        generation, population, births, deaths, bounds, centre
+//! {"command": "cells"}                        "cells": [[x, y, z], ...]
+//! {"command": "set", "cells": [[1, 2, 3]], "alive": true}
+//! {"command": "rule"}                         the rule, or sets it if "rule" is given
+//! {"command": "step", "generations": 10}
+//! {"command": "pause"}, {"command": "run"}    "paused": true or false
+//! ```
+//!
+//! Connections are served on their own threads; requests reach the world
+//! through `Server::poll`, which the main loop calls between frames. A
+//! request longer than `MAX_LINE` bytes is answered with an error and the
+//! connection closed.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+#[cfg(unix)]
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+use json::Json;
+use rule::Rule;
+use stats::Tracker;
+use world::{World, I3d};
+
+/// Longest request line read, in bytes, newline included.
+pub const MAX_LINE: usize = 1 << 22;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Address {
+    /// Path of a Unix domain socket.
+    Unix(String),
+    /// Port on 127.0.0.1.
+    Tcp(u16),
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Address::Unix(ref path) => write!(f, "unix:{}", path),
+            Address::Tcp(port) => write!(f, "tcp:{}", port),
+        }
+    }
+}
+
+impl FromStr for Address {
+    type Err = String;
+
+    /// Accepts `unix:PATH`, `tcp:PORT`, a bare port number or a path.
+    fn from_str(s: &str) -> Result<Address, String> {
+        if s.starts_with("unix:") {
+            Ok(Address::Unix(s[5..].to_string()))
+        } else if s.starts_with("tcp:") {
+            s[4..].parse().map(Address::Tcp).map_err(|_| format!("invalid port: {}", &s[4..]))
+        } else if let Ok(port) = s.parse() {
+            Ok(Address::Tcp(port))
+        } else if !s.is_empty() {
+            Ok(Address::Unix(s.to_string()))
+        } else {
+            Err(String::from("empty control address"))
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Request {
+    Stats,
+    Cells,
+    Set(Vec<I3d>, bool),
+    /// Sets the rule if given, and reports it.
+    Rule(Option<Rule>),
+    Step(u64),
+    Pause,
+    Run,
+}
+
+fn parse_cell(cell: &Json) -> Option<I3d> {
+    match cell.as_array() {
+        Some(c) if c.len() == 3 => match (c[0].as_i64(), c[1].as_i64(), c[2].as_i64()) {
+            (Some(x), Some(y), Some(z)) => Some((x, y, z)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl Request {
+    pub fn parse(request: &Json) -> Result<Request, String> {
+        let command = try!(request.get("command").and_then(|c| c.as_str())
+            .ok_or(String::from("missing \"command\"")));
+        match command {
+            "stats" => Ok(Request::Stats),
+            "cells" => Ok(Request::Cells),
+            "set" => {
+                let cells = try!(request.get("cells").and_then(|c| c.as_array())
+                    .ok_or(String::from("set needs \"cells\": [[x, y, z], ...]")));
+                let cells = try!(cells.iter().map(parse_cell).collect::<Option<Vec<_>>>()
+                    .ok_or(String::from("cells must be arrays of three integers")));
+                let alive = match request.get("alive") {
+                    None => true,
+                    Some(a) => try!(a.as_bool().ok_or(String::from("\"alive\" must be true or false"))),
+                };
+                Ok(Request::Set(cells, alive))
+            },
+            "rule" => match request.get("rule") {
+                None => Ok(Request::Rule(None)),
+                Some(rule) => {
+                    let rule = try!(rule.as_str().ok_or(String::from("\"rule\" must be a string")));
+                    Rule::parse(rule).map(|r| Request::Rule(Some(r))).map_err(|e| format!("{}: {}", rule, e))
+                },
+            },
+            "step" => match request.get("generations") {
+                None => Ok(Request::Step(1)),
+                Some(n) => match n.as_i64() {
+                    Some(n) if n >= 0 => Ok(Request::Step(n as u64)),
+                    _ => Err(String::from("\"generations\" must be a non-negative integer")),
+                },
+            },
+            "pause" => Ok(Request::Pause),
+            "run" => Ok(Request::Run),
+            _ => Err(format!("unknown command: {}", command)),
+        }
+    }
+}
+
+/// Members of a successful reply, after `"ok": true`.
+pub type Reply = Vec<(String, Json)>;
+
+fn member(key: &str, value: Json) -> (String, Json) {
+    (key.to_string(), value)
+}
+
+fn point(x: f64, y: f64, z: f64) -> Json {
+    Json::Array(vec![Json::Number(x), Json::Number(y), Json::Number(z)])
+}
+
+/// A request waiting for the main loop.
+pub struct Call {
+    pub request: Request,
+    id: Option<Json>,
+    reply: Sender<String>,
+}
+
+impl Call {
+    /// Sends the reply to the client, who may have gone already.
+    pub fn answer(self, result: Result<Reply, String>) {
+        let _ = self.reply.send(reply_line(self.id, result));
+    }
+}
+
+fn reply_line(id: Option<Json>, result: Result<Reply, String>) -> String {
+    let mut members = Vec::new();
+    if let Some(id) = id {
+        members.push(member("id", id));
+    }
+    match result {
+        Ok(reply) => {
+            members.push(member("ok", Json::Bool(true)));
+            members.extend(reply);
+        },
+        Err(e) => {
+            members.push(member("ok", Json::Bool(false)));
+            members.push(member("error", Json::String(e)));
+        },
+    }
+    Json::Object(members).to_string()
+}
+
+/// Answers requests for one client until it disconnects.
+fn serve<S: io::Read + Write>(reader: S, mut writer: S, calls: Sender<Call>) {
+    let mut reader = BufReader::new(reader);
+    loop {
+        let mut line = String::new();
+        match (&mut reader).take(MAX_LINE as u64).read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(n) if n == MAX_LINE && !line.ends_with('\n') => {
+                let error = format!("request is longer than {} bytes", MAX_LINE);
+                let _ = writeln!(writer, "{}", reply_line(None, Err(error)));
+                return;
+            },
+            Ok(_) => (),
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parsed = Json::parse(&line);
+        let id = parsed.as_ref().ok().and_then(|r| r.get("id")).cloned();
+        let reply = match parsed.and_then(|r| Request::parse(&r)) {
+            Ok(request) => {
+                let (sender, receiver) = mpsc::channel();
+                if calls.send(Call { request: request, id: id, reply: sender }).is_err() {
+                    return;
+                }
+                match receiver.recv() {
+                    Ok(reply) => reply,
+                    Err(_) => return,
+                }
+            },
+            Err(e) => reply_line(id, Err(e)),
+        };
+        if writeln!(writer, "{}", reply).is_err() {
+            return;
+        }
+    }
+}
+
+/// Serves every connection on a thread of its own.
+fn accept<S, I, C>(incoming: I, try_clone: C, calls: Sender<Call>)
+    where S: io::Read + Write + Send + 'static, I: Iterator<Item = io::Result<S>>, C: Fn(&S) -> io::Result<S>
+{
+    for stream in incoming {
+        if let Ok((reader, writer)) = stream.and_then(|s| try_clone(&s).map(|w| (s, w))) {
+            let calls = calls.clone();
+            thread::spawn(move || serve(reader, writer, calls));
+        }
+    }
+}
+
+pub struct Server {
+    calls: Receiver<Call>,
+    tracker: Tracker,
+}
+
+impl Server {
+    /// Starts listening on `address`. A stale Unix socket file is replaced,
+    /// but any other file in its place is left alone and reported.
+    pub fn start(address: &Address) -> io::Result<Server> {
+        let (sender, receiver) = mpsc::channel();
+        match *address {
+            Address::Tcp(port) => {
+                let listener = try!(TcpListener::bind(("127.0.0.1", port)));
+                thread::spawn(move || accept(listener.incoming(), TcpStream::try_clone, sender));
+            },
+            #[cfg(unix)]
+            Address::Unix(ref path) => {
+                match fs::symlink_metadata(path) {
+                    Ok(ref metadata) if metadata.file_type().is_socket() => try!(fs::remove_file(path)),
+                    Ok(_) => {
+                        let message = format!("{} already exists and is not a socket", path);
+                        return Err(io::Error::new(io::ErrorKind::AddrInUse, message));
+                    },
+                    Err(ref e) if e.kind() == io::ErrorKind::NotFound => {},
+                    Err(e) => return Err(e),
+                }
+                let listener = try!(UnixListener::bind(path));
+                thread::spawn(move || accept(listener.incoming(), UnixStream::try_clone, sender));
+            },
+            #[cfg(not(unix))]
+            Address::Unix(_) => {
+                return Err(io::Error::new(io::ErrorKind::Other, "Unix sockets are not supported here"));
+            },
+        }
+        Ok(Server { calls: receiver, tracker: Tracker::new() })
+    }
+
+    /// Next request waiting to be answered, if any.
+    pub fn poll(&self) -> Option<Call> {
+        match self.calls.try_recv() {
+            Ok(call) => Some(call),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+
+    /// Carries out a request on `world`. `Pause` and `Run` concern the main
+    /// loop and are left to the caller; they give an empty reply here.
+    pub fn handle<W: World + ?Sized>(&mut self, world: &mut W, request: &Request) -> Result<Reply, String> {
+        match *request {
+            Request::Stats => {
+                let stats = self.tracker.record(&*world);
+                let corner = |(x, y, z): I3d| point(x as f64, y as f64, z as f64);
+                Ok(vec![
+                    member("generation", Json::Number(stats.generation as f64)),
+                    member("population", Json::Number(stats.population as f64)),
+                    member("births", Json::Number(stats.births as f64)),
+                    member("deaths", Json::Number(stats.deaths as f64)),
+                    member("bounding_box", stats.bounding_box.map_or(Json::Null, |(low, high)| {
+                        Json::Array(vec![corner(low), corner(high)])
+                    })),
+                    member("centre_of_mass", stats.centre_of_mass.map_or(Json::Null, |(x, y, z)| point(x, y, z))),
+                ])
+            },
+            Request::Cells => {
+                let mut cells: Vec<_> = world.live_cells().collect();
+                cells.sort();
+                let cells = cells.into_iter().map(|(x, y, z)| point(x as f64, y as f64, z as f64)).collect();
+                Ok(vec![member("cells", Json::Array(cells))])
+            },
+            Request::Set(ref cells, alive) => {
+                if let Some(&(x, y, z)) = cells.iter().find(|&&p| !world.contains(p)) {
+                    return Err(format!("cell {},{},{} lies outside the world", x, y, z));
+                }
+                for &p in cells.iter() {
+                    world.set(p, alive);
+                }
+                Ok(vec![member("population", Json::Number(world.population() as f64))])
+            },
+            Request::Rule(rule) => {
+                if let Some(rule) = rule {
+                    try!(world.check_rule(&rule));
+                    world.set_rule(rule);
+                }
+                Ok(vec![member("rule", Json::String(world.rule().to_string()))])
+            },
+            Request::Step(n) => {
+                for _ in 0..n {
+                    world.step();
+                }
+                Ok(vec![
+                    member("generation", Json::Number(world.generation() as f64)),
+                    member("population", Json::Number(world.population() as f64)),
+                ])
+            },
+            Request::Pause | Request::Run => Ok(Vec::new()),
+        }
+    }
+}
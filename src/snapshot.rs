@@ -0,0 +1,134 @@
+//! Versioned binary snapshots of a `State`, all numbers little-endian:
+//!
+//! | bytes | contents                                               |
+//! |-------|--------------------------------------------------------|
+//! | 4     | magic `GOL3`                                           |
+//! | 2     | format version, currently 1                            |
+//! | 12    | size along x, y and z, `u32` each                      |
+//! | 8     | birth and stay masks of the rule, `u32` each           |
+//! | 3     | boundary of each axis: periodic, dead, alive, mirror   |
+//! | 8     | generation                                             |
+//! | 1 + 8 | 1 and the soup seed, or 0 and 0 if there is none       |
+//! | 8n    | the packed words, see `State::words`                   |
+//! | 8     | checksum of the words                                  |
+
+use std::io::{self, Read, Write};
+
+use boundary::Boundary;
+use packed;
+use rule::{Rule, MOORE_NEIGHBOURS};
+use state::State;
+
+const MAGIC: &'static [u8; 4] = b"GOL3";
+pub const VERSION: u16 = 1;
+
+const BOUNDARIES: [Boundary; 4] = [Boundary::Periodic, Boundary::Dead, Boundary::Alive, Boundary::Mirror];
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn write_le<W: Write>(writer: &mut W, value: u64, bytes: usize) -> io::Result<()> {
+    let mut buf = [0; 8];
+    for (i, b) in buf.iter_mut().enumerate().take(bytes) {
+        *b = (value >> (8 * i)) as u8;
+    }
+    writer.write_all(&buf[..bytes])
+}
+
+fn read_le<R: Read>(reader: &mut R, bytes: usize) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    try!(reader.read_exact(&mut buf[..bytes]));
+    Ok(buf[..bytes].iter().rev().fold(0, |v, &b| v << 8 | b as u64))
+}
+
+fn checksum(words: &[u64]) -> u64 {
+    words.iter().fold(0, |h, &w| packed::mix(h ^ w))
+}
+
+impl State {
+    /// Writes a snapshot of the world, its rule, boundaries, generation and
+    /// seed.
+    pub fn save_snapshot<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let (xs, ys, zs) = self.dim();
+        try!(writer.write_all(MAGIC));
+        try!(write_le(&mut writer, VERSION as u64, 2));
+        for &size in [xs, ys, zs].iter() {
+            try!(write_le(&mut writer, size as u64, 4));
+        }
+        try!(write_le(&mut writer, self.rule().birth_mask() as u64, 4));
+        try!(write_le(&mut writer, self.rule().stay_mask() as u64, 4));
+        for b in self.boundaries().iter() {
+            let code = BOUNDARIES.iter().position(|c| c == b).unwrap();
+            try!(write_le(&mut writer, code as u64, 1));
+        }
+        try!(write_le(&mut writer, self.generation(), 8));
+        try!(write_le(&mut writer, self.seed().is_some() as u64, 1));
+        try!(write_le(&mut writer, self.seed().unwrap_or(0), 8));
+        for &word in self.words() {
+            try!(write_le(&mut writer, word, 8));
+        }
+        write_le(&mut writer, checksum(self.words()), 8)
+    }
+
+    /// Reads a snapshot written by `save_snapshot`. The world runs on one
+    /// thread, as after `new`.
+    pub fn load_snapshot<R: Read>(mut reader: R) -> io::Result<State> {
+        let mut magic = [0; 4];
+        try!(reader.read_exact(&mut magic));
+        if &magic != MAGIC {
+            return Err(invalid_data(String::from("not a snapshot")));
+        }
+        let version = try!(read_le(&mut reader, 2));
+        if version != VERSION as u64 {
+            return Err(invalid_data(format!("unsupported snapshot version {}", version)));
+        }
+        let mut dim = [0; 3];
+        for size in dim.iter_mut() {
+            *size = try!(read_le(&mut reader, 4)) as usize;
+        }
+        if dim.iter().any(|&s| s == 0) {
+            return Err(invalid_data(format!("invalid size {}x{}x{}", dim[0], dim[1], dim[2])));
+        }
+        let birth = try!(read_le(&mut reader, 4)) as u32;
+        let stay = try!(read_le(&mut reader, 4)) as u32;
+        if (birth | stay) >> (MOORE_NEIGHBOURS + 1) != 0 {
+            return Err(invalid_data(String::from("rule counts beyond 26 neighbours")));
+        }
+        let counts = |mask: u32| (0..MOORE_NEIGHBOURS + 1).filter(|n| mask >> n & 1 != 0).collect::<Vec<_>>();
+        let rule = Rule::new(&counts(birth), &counts(stay));
+        let mut boundaries = [Boundary::Periodic; 3];
+        for b in boundaries.iter_mut() {
+            let code = try!(read_le(&mut reader, 1)) as usize;
+            *b = try!(BOUNDARIES.get(code).cloned().ok_or(invalid_data(format!("invalid boundary {}", code))));
+        }
+        let generation = try!(read_le(&mut reader, 8));
+        let has_seed = try!(read_le(&mut reader, 1)) != 0;
+        let seed = try!(read_le(&mut reader, 8));
+
+        let wpr = packed::words_per_row(dim[2]);
+        let words_len = try!(dim[0].checked_mul(dim[1])
+            .and_then(|n| n.checked_mul(wpr))
+            .ok_or(invalid_data(String::from("world too large"))));
+        // Grown as the data arrives, so a corrupt size fails on a short read
+        // rather than a huge allocation.
+        let mut words = Vec::with_capacity(words_len.min(1 << 20));
+        for _ in 0..words_len {
+            words.push(try!(read_le(&mut reader, 8)));
+        }
+        if try!(read_le(&mut reader, 8)) != checksum(&words) {
+            return Err(invalid_data(String::from("snapshot is corrupt: checksum mismatch")));
+        }
+        let tail = packed::tail_mask(dim[2]);
+        if words.chunks(wpr).any(|row| row[wpr - 1] & !tail != 0) {
+            return Err(invalid_data(String::from("snapshot is corrupt: cells beyond the world")));
+        }
+
+        let mut state = State::new((dim[0], dim[1], dim[2]), rule);
+        state.set_words(&words);
+        state.set_boundaries(boundaries);
+        state.set_generation(generation);
+        state.set_seed(if has_seed { Some(seed) } else { None });
+        Ok(state)
+    }
+}
@@ -0,0 +1,150 @@
+//! Reproducible random initial conditions.
+
+use std::fmt;
+
+use rand::{self, Rng, SeedableRng, XorShiftRng};
+
+use packed;
+use state::State;
+
+/// Part of the world that receives random cells.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Region {
+    Whole,
+    /// Centred box spanning the given fraction of every axis.
+    Box(f64),
+    /// Centred ellipsoid whose radius along every axis is the given fraction
+    /// of half that axis, i.e. a sphere in a cubic world.
+    Sphere(f64),
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Region::Whole => write!(f, "whole"),
+            Region::Box(r) => write!(f, "box:{}", r),
+            Region::Sphere(r) => write!(f, "sphere:{}", r),
+        }
+    }
+}
+
+/// Recipe for a random soup. The same recipe always yields the same cells
+/// for the same world size.
+#[derive(Copy, Clone, Debug)]
+pub struct Soup {
+    seed: u64,
+    density: f64,
+    region: Region,
+    skip_every: Option<usize>,
+}
+
+/// Expands a 64-bit seed into a xorshift state (splitmix64), which must not
+/// be all zeroes.
+fn rng_from_seed(seed: u64) -> XorShiftRng {
+    let next = |i: u64| packed::mix(seed.wrapping_add(0x9E3779B97F4A7C15u64.wrapping_mul(i)));
+    let (a, b) = (next(1), next(2));
+    let mut words = [a as u32, (a >> 32) as u32, b as u32, (b >> 32) as u32];
+    if words.iter().all(|&w| w == 0) {
+        words[0] = 1;
+    }
+    XorShiftRng::from_seed(words)
+}
+
+impl Soup {
+    /// The classic recipe: density 0.5 in the inner 3/4 box, every 5th
+    /// plane left empty.
+    pub fn new(seed: u64) -> Soup {
+        Soup {
+            seed: seed,
+            density: 0.5,
+            region: Region::Box(0.75),
+            skip_every: Some(5),
+        }
+    }
+
+    /// A seed taken from the thread RNG.
+    pub fn random_seed() -> u64 {
+        rand::random()
+    }
+
+    /// The same recipe with the following seed, for reseeding.
+    pub fn next_seed(mut self) -> Soup {
+        self.seed = self.seed.wrapping_add(1);
+        self
+    }
+
+    pub fn with_density(mut self, density: f64) -> Soup {
+        self.density = density;
+        self
+    }
+
+    pub fn with_region(mut self, region: Region) -> Soup {
+        self.region = region;
+        self
+    }
+
+    /// Leaves every cell with a coordinate divisible by `n` empty.
+    pub fn with_lattice_skip(mut self, n: Option<usize>) -> Soup {
+        self.skip_every = n;
+        self
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn density(&self) -> f64 {
+        self.density
+    }
+
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    pub fn lattice_skip(&self) -> Option<usize> {
+        self.skip_every
+    }
+
+    fn in_region(&self, (xs, ys, zs): (usize, usize, usize), (x, y, z): (usize, usize, usize)) -> bool {
+        if let Some(n) = self.skip_every {
+            if n > 0 && (x % n == 0 || y % n == 0 || z % n == 0) {
+                return false;
+            }
+        }
+        match self.region {
+            Region::Whole => true,
+            Region::Box(fraction) => {
+                let inside = |c: usize, size: usize| {
+                    let margin = ((1.0 - fraction) / 2.0 * size as f64) as usize;
+                    margin <= c && c <= size - margin
+                };
+                inside(x, xs) && inside(y, ys) && inside(z, zs)
+            },
+            Region::Sphere(fraction) => {
+                let d = |c: usize, size: usize| {
+                    let r = fraction * size as f64 / 2.0;
+                    (c as f64 - (size - 1) as f64 / 2.0) / r
+                };
+                let (dx, dy, dz) = (d(x, xs), d(y, ys), d(z, zs));
+                dx * dx + dy * dy + dz * dz <= 1.0
+            },
+        }
+    }
+
+    /// Randomises every cell inside the region; cells outside are left as
+    /// they are. Records the seed in `state`.
+    pub fn fill(&self, state: &mut State) {
+        let mut rng = rng_from_seed(self.seed);
+        let (xs, ys, zs) = state.dim();
+        for x in 0..xs {
+            for y in 0..ys {
+                for z in 0..zs {
+                    if self.in_region((xs, ys, zs), (x, y, z)) {
+                        state.set((x, y, z), rng.gen::<f64>() < self.density);
+                    }
+                }
+            }
+        }
+        state.set_seed(Some(self.seed));
+    }
+}
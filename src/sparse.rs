@@ -0,0 +1,220 @@
+//! Unbounded world made of fixed-size chunks kept in a hash map. Only chunks
+//! holding live cells are stored, and only those and their neighbours are
+//! stepped, so patterns can travel and grow without limit.
+
+extern crate std;
+
+use std::collections::{HashMap, HashSet};
+
+use packed;
+use rule::Rule;
+use world::{self, World, I3d};
+
+/// Chunk size along x and y.
+pub const CHUNK_XY: i64 = 8;
+/// Chunk size along z: one word per row.
+pub const CHUNK_Z: i64 = packed::WORD_BITS as i64;
+
+const CHUNK_ROWS: usize = (CHUNK_XY * CHUNK_XY) as usize;
+
+/// Chunk coordinates: cell coordinates divided by the chunk size, rounded
+/// down.
+pub type ChunkKey = (i64, i64, i64);
+
+/// Rows indexed by `x * CHUNK_XY + y`, bit `z` of each word a cell.
+type Chunk = [u64; CHUNK_ROWS];
+
+/// Splits a coordinate into chunk index and offset within the chunk.
+#[inline]
+fn split(c: i64, size: i64) -> (i64, usize) {
+    let local = (c % size + size) % size;
+    ((c - local) / size, local as usize)
+}
+
+pub struct SparseWorld {
+    chunks: HashMap<ChunkKey, Box<Chunk>>,
+    rule: Rule,
+    generation: u64,
+    changed: bool,
+}
+
+impl SparseWorld {
+    /// # Panics
+    ///
+    /// Panics if the rule gives birth with 0 neighbours, which would fill
+    /// all of space.
+    pub fn new(rule: Rule) -> SparseWorld {
+        assert!(!rule.next_state(false, 0), "B0 rules cannot be simulated in an unbounded world");
+        SparseWorld {
+            chunks: HashMap::new(),
+            rule: rule,
+            generation: 0,
+            changed: true,
+        }
+    }
+
+    pub fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    /// Number of chunks currently allocated.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Smallest and largest coordinates of live cells, `None` if the world
+    /// is empty.
+    pub fn bounding_box(&self) -> Option<(I3d, I3d)> {
+        self.live_cells().fold(None, |bb, (x, y, z)| match bb {
+            None => Some(((x, y, z), (x, y, z))),
+            Some((lo, hi)) => Some((
+                (std::cmp::min(lo.0, x), std::cmp::min(lo.1, y), std::cmp::min(lo.2, z)),
+                (std::cmp::max(hi.0, x), std::cmp::max(hi.1, y), std::cmp::max(hi.2, z)),
+            )),
+        })
+    }
+
+    /// Next generation of chunk `key`, computed from the chunks around it.
+    fn step_chunk(&self, (cx, cy, cz): ChunkKey) -> Chunk {
+        let mut near: [Option<&Chunk>; 27] = [None; 27];
+        for dx in -1..2 {
+            for dy in -1..2 {
+                for dz in -1..2 {
+                    let i = ((dx + 1) * 9 + (dy + 1) * 3 + dz + 1) as usize;
+                    near[i] = self.chunks.get(&(cx + dx, cy + dy, cz + dz)).map(|c| &**c);
+                }
+            }
+        }
+        let word = |ox: i64, oy: i64, oz: i64, row: usize| {
+            near[((ox + 1) * 9 + (oy + 1) * 3 + oz + 1) as usize].map_or(0, |c| c[row])
+        };
+
+        let mut out = [0; CHUNK_ROWS];
+        let mut rows = Vec::with_capacity(9);
+        for lx in 0..CHUNK_XY {
+            for ly in 0..CHUNK_XY {
+                rows.clear();
+                let mut centre = 0;
+                for dx in -1..2 {
+                    for dy in -1..2 {
+                        if dx == 0 && dy == 0 {
+                            centre = rows.len();
+                        }
+                        let (ox, nx) = split(lx + dx, CHUNK_XY);
+                        let (oy, ny) = split(ly + dy, CHUNK_XY);
+                        let row = nx * CHUNK_XY as usize + ny;
+                        rows.push((
+                            word(ox, oy, 0, row),
+                            word(ox, oy, -1, row) >> 63 != 0,
+                            word(ox, oy, 1, row) & 1 != 0,
+                        ));
+                    }
+                }
+                out[(lx * CHUNK_XY + ly) as usize] = packed::step_word(&rows, centre, &self.rule);
+            }
+        }
+        out
+    }
+}
+
+impl World for SparseWorld {
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// # Panics
+    ///
+    /// Panics on B0 rules, see `SparseWorld::new`.
+    fn set_rule(&mut self, rule: Rule) {
+        assert!(!rule.next_state(false, 0), "B0 rules cannot be simulated in an unbounded world");
+        self.rule = rule;
+    }
+
+    fn check_rule(&self, rule: &Rule) -> Result<(), String> {
+        world::check_unbounded(rule)
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn step(&mut self) {
+        let mut candidates = HashSet::new();
+        for &(cx, cy, cz) in self.chunks.keys() {
+            for dx in -1..2 {
+                for dy in -1..2 {
+                    for dz in -1..2 {
+                        candidates.insert((cx + dx, cy + dy, cz + dz));
+                    }
+                }
+            }
+        }
+
+        let mut chunks = HashMap::with_capacity(self.chunks.len());
+        let mut changed = false;
+        for key in candidates {
+            let next = self.step_chunk(key);
+            let old = self.chunks.get(&key);
+            changed = changed || match old {
+                Some(old) => old[..] != next[..],
+                None => next.iter().any(|&w| w != 0),
+            };
+            if next.iter().any(|&w| w != 0) {
+                chunks.insert(key, Box::new(next));
+            }
+        }
+        self.chunks = chunks;
+        self.changed = changed;
+        self.generation += 1;
+    }
+
+    fn is_alive(&self, (x, y, z): I3d) -> bool {
+        let (cx, lx) = split(x, CHUNK_XY);
+        let (cy, ly) = split(y, CHUNK_XY);
+        let (cz, lz) = split(z, CHUNK_Z);
+        self.chunks.get(&(cx, cy, cz))
+            .map_or(false, |c| c[lx * CHUNK_XY as usize + ly] >> lz & 1 != 0)
+    }
+
+    fn set(&mut self, (x, y, z): I3d, alive: bool) {
+        let (cx, lx) = split(x, CHUNK_XY);
+        let (cy, ly) = split(y, CHUNK_XY);
+        let (cz, lz) = split(z, CHUNK_Z);
+        let key = (cx, cy, cz);
+        if !alive && !self.chunks.contains_key(&key) {
+            return;
+        }
+        let empty = {
+            let chunk = self.chunks.entry(key).or_insert_with(|| Box::new([0; CHUNK_ROWS]));
+            packed::set_bit(&mut chunk[lx * CHUNK_XY as usize + ly..], lz, alive);
+            chunk.iter().all(|&w| w == 0)
+        };
+        if empty {
+            self.chunks.remove(&key);
+        }
+        self.changed = true;
+    }
+
+    fn population(&self) -> usize {
+        self.chunks.values()
+            .flat_map(|c| c.iter())
+            .map(|w| w.count_ones() as usize)
+            .sum()
+    }
+
+    fn live_cells<'a>(&'a self) -> Box<Iterator<Item = I3d> + 'a> {
+        Box::new(self.chunks.iter().flat_map(|(&(cx, cy, cz), chunk)| {
+            chunk.iter().enumerate().flat_map(move |(row, &word)| {
+                let x = cx * CHUNK_XY + row as i64 / CHUNK_XY;
+                let y = cy * CHUNK_XY + row as i64 % CHUNK_XY;
+                (0..CHUNK_Z)
+                    .filter(move |&b| word >> b & 1 != 0)
+                    .map(move |b| (x, y, cz * CHUNK_Z + b))
+            })
+        }))
+    }
+
+    fn is_static(&self) -> bool {
+        !self.changed
+    }
+}
@@ -0,0 +1,415 @@
+extern crate std;
+
+use crossbeam;
+use num_cpus;
+
+use boundary::{Boundary, Boundaries};
+use packed::{self, Counter, Row};
+use rule::Rule;
+use world::{World, I3d};
+
+pub type U3d = (usize, usize, usize);
+
+/// Fewest rows a worker thread is given; smaller slabs step faster than a
+/// thread starts.
+const MIN_ROWS_PER_THREAD: usize = 256;
+
+#[inline]
+pub fn i2p((_, ys, zs): U3d, idx: usize) -> U3d {
+    (idx / (zs * ys),  (idx / zs) % ys, idx % zs)
+}
+
+#[inline]
+pub fn p2i((_, ys, zs): U3d, (x, y, z): U3d) -> usize {
+    x * zs * ys + y * zs + z
+}
+
+/// Number of neighbours a cell has in a world of size `dim`: axes of size 1
+/// are flat and contribute no neighbours, so a `(xs, ys, 1)` slab has the 8
+/// of 2D Life.
+pub fn neighbourhood_size((xs, ys, zs): U3d) -> u32 {
+    let span = |size: usize| if size == 1 { 1 } else { 3 };
+    span(xs) * span(ys) * span(zs) - 1
+}
+
+/// Calls `f` with the coordinates of every row in the x/y neighbourhood of
+/// row `(x, y)`, `None` for rows beyond an edge, the row itself included.
+#[inline]
+fn for_each_neighbour_row<F>((xs, ys, _): U3d, boundaries: Boundaries, x: usize, y: usize, mut f: F)
+    where F: FnMut(isize, isize, Option<usize>, Option<usize>) {
+    let offsets = |size: usize| if size == 1 { 0..1 } else { -1..2 };
+    for dx in offsets(xs) {
+        for dy in offsets(ys) {
+            f(dx, dy, boundaries[0].resolve(x as isize + dx, xs), boundaries[1].resolve(y as isize + dy, ys));
+        }
+    }
+}
+
+/// Computes the rows of `out`, which start at `x0`, from `old`. Rows whose
+/// flag in `active` is clear are copied unchanged.
+fn step_slab(old: &[u64], dim: U3d, boundaries: Boundaries, rule: &Rule, x0: usize,
+             active: Option<&[bool]>, counters: &mut Vec<Counter>, out: &mut [u64]) {
+    let (_, ys, zs) = dim;
+    let (bx, by, bz) = (boundaries[0], boundaries[1], boundaries[2]);
+    let wpr = packed::words_per_row(zs);
+    let mut rows = Vec::with_capacity(9);
+    for (i, out_row) in out.chunks_mut(wpr).enumerate() {
+        let (x, y) = (x0 + i / ys, i % ys);
+        if let Some(active) = active {
+            if !active[x * ys + y] {
+                let start = (x * ys + y) * wpr;
+                out_row.copy_from_slice(&old[start..start + wpr]);
+                continue;
+            }
+        }
+        rows.clear();
+        let mut centre = 0;
+        for_each_neighbour_row(dim, boundaries, x, y, |dx, dy, nx, ny| {
+            if dx == 0 && dy == 0 {
+                centre = rows.len();
+            }
+            rows.push(match (nx, ny) {
+                (Some(nx), Some(ny)) => {
+                    let start = (nx * ys + ny) * wpr;
+                    Row::Cells(&old[start..start + wpr])
+                },
+                (nx, ny) => Row::Wall(
+                    (nx.is_some() || bx.wall_alive()) && (ny.is_some() || by.wall_alive())
+                ),
+            });
+        });
+        packed::step_row(&rows, centre, zs, bz, rule, counters, out_row);
+    }
+}
+
+/// 3D world stored bit-packed along z (see `packed`) and stepped with a
+/// birth/stay `Rule`.
+pub struct State {
+    dim: U3d,
+    /// Words per (x, y) row.
+    wpr: usize,
+    world: Vec<u64>,
+    old_world: Vec<u64>,
+    rule: Rule,
+    boundaries: Boundaries,
+    generation: u64,
+    seed: Option<u64>,
+    threads: usize,
+    /// Scratch space of every slab, kept between steps.
+    counters: Vec<Vec<Counter>>,
+    track_active: bool,
+    /// Rows that differ from the previous generation, or were edited.
+    changed_rows: Vec<bool>,
+    /// Evaluate every row on the next step, as the rule or boundaries changed.
+    full_sweep: bool,
+    evaluated_cells: usize,
+}
+
+impl State {
+    /// Creates an empty (all dead) world of the given size with periodic
+    /// boundaries.
+    pub fn new((xs, ys, zs): U3d, rule: Rule) -> State {
+        let wpr = packed::words_per_row(zs);
+        let world = vec![0; xs * ys * wpr];
+        State {
+            dim: (xs, ys, zs),
+            wpr: wpr,
+            old_world: world.clone(),
+            world: world,
+            rule: rule,
+            boundaries: [Boundary::Periodic; 3],
+            generation: 0,
+            seed: None,
+            threads: 1,
+            counters: Vec::new(),
+            track_active: true,
+            changed_rows: vec![false; xs * ys],
+            full_sweep: true,
+            evaluated_cells: 0,
+        }
+    }
+
+    pub fn dim(&self) -> U3d {
+        self.dim
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        self.full_sweep = true;
+    }
+
+    /// Fails if `rule` refers to more neighbours than a cell of this world
+    /// has, e.g. B9 in a 2D slab.
+    pub fn check_rule(&self, rule: &Rule) -> Result<(), String> {
+        let neighbours = self.neighbourhood_size();
+        match rule.max_count() {
+            Some(n) if n > neighbours => Err(format!(
+                "{} counts up to {} neighbours, but cells of this world have {}", rule, n, neighbours)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn boundaries(&self) -> Boundaries {
+        self.boundaries
+    }
+
+    /// A cell beyond an edge is alive only if every edge it lies beyond is
+    /// an `Alive` wall.
+    pub fn set_boundaries(&mut self, boundaries: Boundaries) {
+        self.boundaries = boundaries;
+        self.full_sweep = true;
+    }
+
+    /// Number of generations stepped since creation.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    /// Seed of the soup the world was filled with, if any.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    #[inline]
+    fn row(&self, x: usize, y: usize) -> &[u64] {
+        let start = (x * self.dim.1 + y) * self.wpr;
+        &self.world[start..start + self.wpr]
+    }
+
+    pub fn is_alive(&self, (x, y, z): U3d) -> bool {
+        packed::get_bit(self.row(x, y), z)
+    }
+
+    pub fn set(&mut self, (x, y, z): U3d, alive: bool) {
+        assert!(x < self.dim.0 && y < self.dim.1 && z < self.dim.2);
+        let start = (x * self.dim.1 + y) * self.wpr;
+        packed::set_bit(&mut self.world[start..start + self.wpr], z, alive);
+        self.changed_rows[x * self.dim.1 + y] = true;
+    }
+
+    /// Kills every cell.
+    pub fn clear(&mut self) {
+        for word in self.world.iter_mut() {
+            *word = 0;
+        }
+        self.full_sweep = true;
+    }
+
+    pub fn population(&self) -> usize {
+        self.world.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Number of cells that differ from the previous generation.
+    pub fn changed_cells(&self) -> usize {
+        self.world.iter()
+            .zip(self.old_world.iter())
+            .map(|(a, b)| (a ^ b).count_ones() as usize)
+            .sum()
+    }
+
+    /// The packed words, row by row (see `packed`).
+    pub fn words(&self) -> &[u64] {
+        &self.world
+    }
+
+    /// Replaces every cell with the packed rows `words`, laid out as
+    /// returned by `words`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `words` has the wrong length or sets bits beyond the end of
+    /// a row.
+    pub fn set_words(&mut self, words: &[u64]) {
+        assert_eq!(words.len(), self.world.len(), "packed world has the wrong length");
+        let tail = packed::tail_mask(self.dim.2);
+        assert!(words.chunks(self.wpr).all(|row| row[self.wpr - 1] & !tail == 0),
+                "packed world has cells beyond the end of a row");
+        self.world.copy_from_slice(words);
+        self.full_sweep = true;
+    }
+
+    /// Cell states in index order (see `i2p`/`p2i`).
+    pub fn cell_states<'a>(&'a self) -> Box<Iterator<Item = bool> + 'a> {
+        let zs = self.dim.2;
+        Box::new(
+            self.world.chunks(self.wpr)
+                .flat_map(move |row| (0..zs).map(move |z| packed::get_bit(row, z)))
+        )
+    }
+
+    /// Coordinates of every live cell in index order.
+    pub fn live_cells<'a>(&'a self) -> Box<Iterator<Item = U3d> + 'a> {
+        let (_, ys, _) = self.dim;
+        let wpr = self.wpr;
+        Box::new(
+            self.world.iter()
+                .enumerate()
+                .filter(|&(_, word)| *word != 0)
+                .flat_map(move |(i, &word)| {
+                    let (row, w) = (i / wpr, i % wpr);
+                    (0..packed::WORD_BITS)
+                        .filter(move |b| word >> b & 1 != 0)
+                        .map(move |b| (row / ys, row % ys, w * packed::WORD_BITS + b))
+                })
+        )
+    }
+
+    pub fn neighbourhood_size(&self) -> u32 {
+        neighbourhood_size(self.dim)
+    }
+
+    /// Number of worker threads `step_forward` splits the world across, at
+    /// most. Small worlds are stepped on fewer.
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Sets the number of worker threads, 0 meaning one per CPU. Results do
+    /// not depend on it.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = if threads == 0 { num_cpus::get() } else { threads };
+    }
+
+    /// Whether steps only re-evaluate rows next to ones that changed in the
+    /// previous step. On by default; results do not depend on it.
+    pub fn set_active_tracking(&mut self, on: bool) {
+        self.track_active = on;
+        self.full_sweep = true;
+    }
+
+    /// Number of cells the last step actually evaluated.
+    pub fn evaluated_cells(&self) -> usize {
+        self.evaluated_cells
+    }
+
+    /// Rows to evaluate this step: those with a changed row around them.
+    fn active_rows(&self) -> Vec<bool> {
+        let (xs, ys, _) = self.dim;
+        let mut active = vec![false; xs * ys];
+        for x in 0..xs {
+            for y in 0..ys {
+                let changed = &self.changed_rows;
+                let mut any = false;
+                for_each_neighbour_row(self.dim, self.boundaries, x, y, |_, _, nx, ny| {
+                    if let (Some(nx), Some(ny)) = (nx, ny) {
+                        any = any || changed[nx * ys + ny];
+                    }
+                });
+                active[x * ys + y] = any;
+            }
+        }
+        active
+    }
+
+    pub fn step_forward(&mut self) {
+        let active = if self.track_active && !self.full_sweep { Some(self.active_rows()) } else { None };
+        self.evaluated_cells = self.dim.2 * match active {
+            Some(ref active) => active.iter().filter(|&&a| a).count(),
+            None => self.dim.0 * self.dim.1,
+        };
+        self.full_sweep = false;
+
+        std::mem::swap(&mut self.world, &mut self.old_world);
+        let (xs, ys, _) = self.dim;
+        let threads = std::cmp::max(1, std::cmp::min(self.threads, xs * ys / MIN_ROWS_PER_THREAD));
+        let slab_width = (xs + threads - 1) / threads;
+        let slabs = if self.world.is_empty() { 1 } else { (xs + slab_width - 1) / slab_width };
+        if self.counters.len() < slabs {
+            self.counters.resize(slabs, Vec::new());
+        }
+        if slabs == 1 {
+            step_slab(&self.old_world, self.dim, self.boundaries, &self.rule, 0,
+                      active.as_ref().map(|a| &a[..]), &mut self.counters[0], &mut self.world);
+        } else {
+            let (dim, boundaries, rule) = (self.dim, self.boundaries, self.rule);
+            let slab_len = slab_width * ys * self.wpr;
+            let old_world = &self.old_world;
+            let world = &mut self.world;
+            let counters = &mut self.counters;
+            let active = active.as_ref().map(|a| &a[..]);
+            crossbeam::scope(|scope| {
+                for (i, (slab, counters)) in world.chunks_mut(slab_len).zip(counters.iter_mut()).enumerate() {
+                    scope.spawn(move || {
+                        step_slab(old_world, dim, boundaries, &rule, i * slab_width, active, counters, slab);
+                    });
+                }
+            });
+        }
+
+        let wpr = self.wpr;
+        for (changed, (new, old)) in self.changed_rows.iter_mut()
+            .zip(self.world.chunks(wpr).zip(self.old_world.chunks(wpr))) {
+            *changed = new != old;
+        }
+        self.generation += 1;
+    }
+}
+
+impl World for State {
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn set_rule(&mut self, rule: Rule) {
+        State::set_rule(self, rule);
+    }
+
+    fn check_rule(&self, rule: &Rule) -> Result<(), String> {
+        State::check_rule(self, rule)
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn step(&mut self) {
+        self.step_forward();
+    }
+
+    /// Cells outside the world are dead.
+    fn is_alive(&self, (x, y, z): I3d) -> bool {
+        World::contains(self, (x, y, z)) && State::is_alive(self, (x as usize, y as usize, z as usize))
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `p` lies outside the world.
+    fn set(&mut self, (x, y, z): I3d, alive: bool) {
+        assert!(x >= 0 && y >= 0 && z >= 0, "cell ({}, {}, {}) lies outside the world", x, y, z);
+        State::set(self, (x as usize, y as usize, z as usize), alive);
+    }
+
+    fn contains(&self, (x, y, z): I3d) -> bool {
+        let (xs, ys, zs) = self.dim;
+        let inside = |c: i64, size: usize| 0 <= c && (c as u64) < size as u64;
+        inside(x, xs) && inside(y, ys) && inside(z, zs)
+    }
+
+    fn population(&self) -> usize {
+        State::population(self)
+    }
+
+    fn live_cells<'a>(&'a self) -> Box<Iterator<Item = I3d> + 'a> {
+        Box::new(State::live_cells(self).map(|(x, y, z)| (x as i64, y as i64, z as i64)))
+    }
+
+    fn is_static(&self) -> bool {
+        self.changed_cells() == 0
+    }
+
+    fn state_hash(&self) -> u64 {
+        self.world.iter().fold(0, |h, &w| packed::mix(h ^ w))
+    }
+}
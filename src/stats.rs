@@ -0,0 +1,180 @@
+//! Per-generation measurements of a world, written as CSV or JSON lines
+//! for plotting.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use world::{World, I3d};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GenerationStats {
+    pub generation: u64,
+    pub population: usize,
+    /// Cells alive now but not in the previously recorded generation.
+    pub births: usize,
+    /// Cells alive in the previously recorded generation but not now.
+    pub deaths: usize,
+    /// Smallest and largest coordinates of live cells.
+    pub bounding_box: Option<(I3d, I3d)>,
+    /// Mean position of the live cells.
+    pub centre_of_mass: Option<(f64, f64, f64)>,
+}
+
+/// Measures successive generations of a world, remembering the live cells
+/// of the last one to count births and deaths.
+#[derive(Default)]
+pub struct Tracker {
+    previous: Option<HashSet<I3d>>,
+}
+
+impl Tracker {
+    pub fn new() -> Tracker {
+        Tracker::default()
+    }
+
+    /// Measures the current generation of `world`. Births and deaths are
+    /// counted against the previous call, so they span every generation in
+    /// between; the first call reports none.
+    pub fn record<W: World + ?Sized>(&mut self, world: &W) -> GenerationStats {
+        let cells: HashSet<I3d> = world.live_cells().collect();
+        let (births, deaths) = match self.previous {
+            Some(ref previous) => (cells.difference(previous).count(), previous.difference(&cells).count()),
+            None => (0, 0),
+        };
+
+        let mut bounding_box: Option<(I3d, I3d)> = None;
+        let mut sum = (0.0, 0.0, 0.0);
+        for &(x, y, z) in cells.iter() {
+            bounding_box = Some(match bounding_box {
+                None => ((x, y, z), (x, y, z)),
+                Some(((lx, ly, lz), (hx, hy, hz))) => (
+                    (lx.min(x), ly.min(y), lz.min(z)),
+                    (hx.max(x), hy.max(y), hz.max(z)),
+                ),
+            });
+            sum = (sum.0 + x as f64, sum.1 + y as f64, sum.2 + z as f64);
+        }
+        let n = cells.len() as f64;
+        let stats = GenerationStats {
+            generation: world.generation(),
+            population: cells.len(),
+            births: births,
+            deaths: deaths,
+            bounding_box: bounding_box,
+            centre_of_mass: if cells.is_empty() { None } else { Some((sum.0 / n, sum.1 / n, sum.2 / n)) },
+        };
+        self.previous = Some(cells);
+        stats
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// Comma-separated values with a header line; fields of an empty world's
+    /// bounding box and centre are left blank.
+    Csv,
+    /// One JSON object per line; an empty world's bounding box and centre
+    /// are `null`.
+    JsonLines,
+}
+
+impl Format {
+    /// Guesses the format from a file name: JSON lines for `.json` and
+    /// `.jsonl`, CSV otherwise.
+    pub fn from_path(path: &str) -> Format {
+        if path.ends_with(".json") || path.ends_with(".jsonl") {
+            Format::JsonLines
+        } else {
+            Format::Csv
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Format::Csv => "csv",
+            Format::JsonLines => "jsonl",
+        })
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "csv" => Ok(Format::Csv),
+            "json" | "jsonl" => Ok(Format::JsonLines),
+            _ => Err(format!("unknown stats format: {}, expected csv or jsonl", s)),
+        }
+    }
+}
+
+/// Writes one line per generation.
+pub struct StatsWriter<W: Write> {
+    writer: W,
+    format: Format,
+    header_written: bool,
+}
+
+impl<W: Write> StatsWriter<W> {
+    pub fn new(writer: W, format: Format) -> StatsWriter<W> {
+        StatsWriter {
+            writer: writer,
+            format: format,
+            header_written: false,
+        }
+    }
+
+    pub fn write(&mut self, stats: &GenerationStats) -> io::Result<()> {
+        match self.format {
+            Format::Csv => self.write_csv(stats),
+            Format::JsonLines => self.write_json(stats),
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_csv(&mut self, s: &GenerationStats) -> io::Result<()> {
+        if !self.header_written {
+            try!(writeln!(self.writer, "generation,population,births,deaths,\
+                                        min_x,min_y,min_z,max_x,max_y,max_z,\
+                                        centre_x,centre_y,centre_z"));
+            self.header_written = true;
+        }
+        try!(write!(self.writer, "{},{},{},{},", s.generation, s.population, s.births, s.deaths));
+        match s.bounding_box {
+            Some(((lx, ly, lz), (hx, hy, hz))) =>
+                try!(write!(self.writer, "{},{},{},{},{},{},", lx, ly, lz, hx, hy, hz)),
+            None => try!(write!(self.writer, ",,,,,,")),
+        }
+        match s.centre_of_mass {
+            Some((x, y, z)) => writeln!(self.writer, "{},{},{}", x, y, z),
+            None => writeln!(self.writer, ",,"),
+        }
+    }
+
+    fn write_json(&mut self, s: &GenerationStats) -> io::Result<()> {
+        let bounding_box = match s.bounding_box {
+            Some(((lx, ly, lz), (hx, hy, hz))) => format!("[[{},{},{}],[{},{},{}]]", lx, ly, lz, hx, hy, hz),
+            None => String::from("null"),
+        };
+        let centre = match s.centre_of_mass {
+            Some((x, y, z)) => format!("[{},{},{}]", x, y, z),
+            None => String::from("null"),
+        };
+        writeln!(self.writer,
+                 "{{\"generation\":{},\"population\":{},\"births\":{},\"deaths\":{},\
+                  \"bounding_box\":{},\"centre_of_mass\":{}}}",
+                 s.generation, s.population, s.births, s.deaths, bounding_box, centre)
+    }
+}
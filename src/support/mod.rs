@@ -4,11 +4,44 @@ extern crate nalgebra;
 extern crate genmesh;
 extern crate std;
 
+use std::env;
 use std::fs::File;
 use std::io::Read;
+use std::path::{Path, PathBuf};
 use self::genmesh::EmitTriangles;
 use glium::{self, Display};
 use glium::vertex::VertexBufferAny;
+use error::StartupError;
+
+/// A shader program in each GLSL version it is written for: the asset names
+/// of its vertex and fragment shaders, and the sources compiled into the
+/// binary for when they are not found on disk. Newest version first.
+pub type Variants = [(&'static str, &'static str, &'static str, &'static str); 3];
+
+pub const MAIN: Variants = [
+    ("shaders/main.vs", "shaders/main.fs",
+     include_str!("../../shaders/main.vs"), include_str!("../../shaders/main.fs")),
+    ("shaders/main_330.vs", "shaders/main_330.fs",
+     include_str!("../../shaders/main_330.vs"), include_str!("../../shaders/main_330.fs")),
+    ("shaders/main_140.vs", "shaders/main_140.fs",
+     include_str!("../../shaders/main_140.vs"), include_str!("../../shaders/main_140.fs")),
+];
+
+pub const PROC_TEX: Variants = [
+    ("shaders/proc_tex.vs", "shaders/proc_tex.fs",
+     include_str!("../../shaders/proc_tex.vs"), include_str!("../../shaders/proc_tex.fs")),
+    ("shaders/proc_tex_330.vs", "shaders/proc_tex_330.fs",
+     include_str!("../../shaders/proc_tex_330.vs"), include_str!("../../shaders/proc_tex_330.fs")),
+    ("shaders/proc_tex_140.vs", "shaders/proc_tex_140.fs",
+     include_str!("../../shaders/proc_tex_140.vs"), include_str!("../../shaders/proc_tex_140.fs")),
+];
+
+/// GLSL versions of the entries of `Variants`.
+const GLSL_VERSIONS: [(u8, u8); 3] = [(4, 0), (3, 3), (1, 4)];
+
+/// How many directories above the executable or the working directory are
+/// searched for assets; `cargo run` puts the binary in `target/debug`.
+const SEARCH_DEPTH: usize = 3;
 
 
 #[derive(Copy, Clone)]
@@ -27,16 +60,17 @@ fn max_by<F>(arr: &[f32], mut cmp: F) -> f32
     max_val
 }
 
-/// Returns a vertex buffer that should be rendered as `TrianglesList`.
-pub fn load_wavefront(display: &Display, data: &[u8], normalize_coords: bool) -> VertexBufferAny {
-    implement_vertex!(Vertex, position, normal, texture);
+implement_vertex!(Vertex, position, normal, texture);
 
+/// Returns a vertex buffer that should be rendered as `TrianglesList`.
+pub fn load_wavefront(display: &Display, data: &[u8], normalize_coords: bool) -> Result<VertexBufferAny, String> {
     let mut data = ::std::io::BufReader::new(data);
     let data = obj::Obj::load(&mut data);
 
     let mut vertex_data = Vec::new();
     let mut max_coord = 0.0f32;
-    for shape in data.object_iter().next().unwrap().group_iter().flat_map(|g| g.indices().iter()) {
+    let object = try!(data.object_iter().next().ok_or(String::from("no object in file")));
+    for shape in object.group_iter().flat_map(|g| g.indices().iter()) {
         shape.emit_triangles(|tri| {
             for v in [tri.x, tri.y, tri.z].iter() {
                 let position = data.position()[v.0];
@@ -55,6 +89,9 @@ pub fn load_wavefront(display: &Display, data: &[u8], normalize_coords: bool) ->
         })
     }
 
+    if vertex_data.is_empty() {
+        return Err(String::from("no faces in file"));
+    }
     if normalize_coords {
         for v in vertex_data.iter_mut() {
             for c in v.position.iter_mut() {
@@ -63,27 +100,147 @@ pub fn load_wavefront(display: &Display, data: &[u8], normalize_coords: bool) ->
         }
     }
 
-    glium::vertex::VertexBuffer::new(display, &*vertex_data.into_boxed_slice()).unwrap().into_vertex_buffer_any()
+    glium::vertex::VertexBuffer::new(display, &*vertex_data.into_boxed_slice())
+        .map(|vb| vb.into_vertex_buffer_any())
+        .map_err(|e| format!("cannot create the vertex buffer: {:?}", e))
 }
 
 pub fn read_from_obj<'a>(display: &glium::Display, path: &'a str, normalize_coords: bool)
-    -> std::io::Result<(glium::vertex::VertexBufferAny, glium::index::NoIndices)> {
+    -> Result<(glium::vertex::VertexBufferAny, glium::index::NoIndices), StartupError> {
     let mut buf = Vec::new();
-    match File::open(path) {
-        Ok(mut f) => f.read_to_end(&mut buf).and_then(
-            |_| Ok((
-                load_wavefront(display, &buf, normalize_coords),
-                glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList)
-            ))
-        ),
-        Err(e) => { println!("cannot open file: {}", e); Err(e) }
-    }
+    try!(File::open(path).and_then(|mut f| f.read_to_end(&mut buf))
+        .map_err(|e| StartupError::Io { path: path.to_string(), error: e }));
+    let vertices = try!(load_wavefront(display, &buf, normalize_coords)
+        .map_err(|e| StartupError::Mesh { path: path.to_string(), message: e }));
+    Ok((vertices, glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList)))
 }
 
-pub fn read_file_content<'a>(path: &'a str) -> std::io::Result<String> {
+pub fn read_file_content<'a>(path: &'a str) -> Result<String, StartupError> {
     let mut content = String::new();
-    match File::open(path) {
-        Ok(mut f) => f.read_to_string(&mut content).and_then(|_| Ok(content)),
-        Err(e) => { println!("cannot open file: {}", e); Err(e) }
+    try!(File::open(path).and_then(|mut f| f.read_to_string(&mut content))
+        .map_err(|e| StartupError::Io { path: path.to_string(), error: e }));
+    Ok(content)
+}
+
+/// Finds asset files such as `shaders/main.vs` under a list of root
+/// directories, the first one holding the file winning.
+pub struct Assets {
+    roots: Vec<PathBuf>,
+}
+
+impl Assets {
+    /// Searches `dir` if given, then the executable's directory, the working
+    /// directory and a few levels above each.
+    pub fn new(dir: Option<&str>) -> Assets {
+        let mut roots: Vec<PathBuf> = dir.into_iter().map(PathBuf::from).collect();
+        let starts = env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)).into_iter()
+            .chain(env::current_dir().ok());
+        for start in starts {
+            let mut dir = Some(start.as_path());
+            for _ in 0..SEARCH_DEPTH + 1 {
+                match dir {
+                    Some(d) => {
+                        roots.push(d.to_path_buf());
+                        dir = d.parent();
+                    },
+                    None => break,
+                }
+            }
+        }
+        Assets { roots: roots }
+    }
+
+    pub fn find(&self, name: &str) -> Option<PathBuf> {
+        self.roots.iter().map(|root| root.join(name)).find(|path| path.is_file())
+    }
+
+    /// Contents of the asset and where they came from, or `fallback` if it
+    /// is nowhere to be found.
+    pub fn read_string(&self, name: &str, fallback: &'static str) -> Result<(String, String), StartupError> {
+        match self.find(name) {
+            Some(path) => {
+                let path = path.to_string_lossy().into_owned();
+                read_file_content(&path).map(|content| (content, path))
+            },
+            None => Ok((String::from(fallback), format!("built-in {}", name))),
+        }
+    }
+
+    /// Compiles the newest variant of a program the context supports:
+    /// GLSL 4.00, 3.30 core or 1.40.
+    pub fn program(&self, display: &glium::Display, variants: &Variants) -> Result<glium::Program, StartupError> {
+        let mut sources = Vec::new();
+        for &(vs, fs, vs_fallback, fs_fallback) in variants.iter() {
+            let (vertex, vs_origin) = try!(self.read_string(vs, vs_fallback));
+            let (fragment, fs_origin) = try!(self.read_string(fs, fs_fallback));
+            sources.push((vertex, fragment, format!("{} and {}", vs_origin, fs_origin)));
+        }
+        let program = program!(display,
+            400 => { vertex: &sources[0].0[..], fragment: &sources[0].1[..] },
+            330 => { vertex: &sources[1].0[..], fragment: &sources[1].1[..] },
+            140 => { vertex: &sources[2].0[..], fragment: &sources[2].1[..] },
+        );
+        program.map_err(|e| {
+            use glium::program::ProgramChooserCreationError::{NoVersion, ProgramCreationError};
+            use glium::ProgramCreationError::{CompilationError, LinkingError};
+            match e {
+                NoVersion => StartupError::Context(String::from(
+                    "the context supports none of GLSL 4.00, 3.30 and 1.40")),
+                ProgramCreationError(e) => {
+                    // program! takes the first version the context supports.
+                    let context = display.get_context();
+                    let chosen = GLSL_VERSIONS.iter()
+                        .position(|&(major, minor)| {
+                            context.is_glsl_version_supported(&glium::Version(glium::Api::Gl, major, minor))
+                        })
+                        .unwrap_or(0);
+                    let log = match e {
+                        CompilationError(log) | LinkingError(log) => log,
+                        e => e.to_string(),
+                    };
+                    StartupError::Shader { files: sources[chosen].2.clone(), log: log }
+                },
+            }
+        })
+    }
+
+    /// The mesh asset, or a generated cube if it is nowhere to be found.
+    pub fn read_mesh(&self, display: &glium::Display, name: &str)
+        -> Result<(glium::vertex::VertexBufferAny, glium::index::NoIndices), StartupError> {
+        match self.find(name) {
+            Some(path) => read_from_obj(display, &path.to_string_lossy(), true),
+            None => cube(display),
+        }
+    }
+}
+
+/// A cube from -1 to 1 along every axis, with flat normals.
+pub fn cube(display: &glium::Display)
+    -> Result<(glium::vertex::VertexBufferAny, glium::index::NoIndices), StartupError> {
+    let mut vertex_data = Vec::with_capacity(36);
+    for axis in 0..3 {
+        for &side in [-1.0f32, 1.0].iter() {
+            // Corners of the face going round counter-clockwise seen from
+            // outside; the next two axes in cyclic order span the face.
+            let mut corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+            if side < 0.0 {
+                corners.reverse();
+            }
+            let vertex = |(u, v): (f32, f32)| {
+                let mut position = [0.0; 3];
+                let mut normal = [0.0; 3];
+                position[axis] = side;
+                position[(axis + 1) % 3] = u;
+                position[(axis + 2) % 3] = v;
+                normal[axis] = side;
+                Vertex { position: position, normal: normal, texture: [(u + 1.0) / 2.0, (v + 1.0) / 2.0] }
+            };
+            for &i in [0, 1, 2, 0, 2, 3].iter() {
+                vertex_data.push(vertex(corners[i]));
+            }
+        }
     }
+    let vertices = try!(glium::vertex::VertexBuffer::new(display, &vertex_data)
+        .map_err(|e| StartupError::context("the cube's vertex buffer", e)));
+    Ok((vertices.into_vertex_buffer_any(), glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList)))
 }
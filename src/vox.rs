@@ -0,0 +1,188 @@
+//! MagicaVoxel `.vox` models. Only the first model of a file is read; its
+//! x, y and z map straight onto the world's, so MagicaVoxel shows the
+//! world's z axis as up.
+//!
+//! A file is `VOX ` and version 150, then a `MAIN` chunk whose children hold
+//! a `SIZE` chunk with the model's extent and an `XYZI` chunk listing every
+//! voxel as x, y, z and palette index, one byte each. Every chunk starts
+//! with its four-byte id, the size of its contents and the size of its
+//! children, all little-endian.
+
+use std::io::{self, Read, Write};
+
+use state::{State, U3d};
+use world::{World, I3d};
+
+const MAGIC: &'static [u8; 4] = b"VOX ";
+const VERSION: u32 = 150;
+/// MagicaVoxel's limit along each axis.
+pub const MAX_SIZE: usize = 256;
+/// Palette index voxels are exported with unless told otherwise.
+pub const DEFAULT_COLOUR: u8 = 1;
+
+/// The first model of a `.vox` file.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Vox {
+    pub size: U3d,
+    /// Position and palette index of every non-empty voxel.
+    pub voxels: Vec<(U3d, u8)>,
+}
+
+impl Vox {
+    /// Positions of the voxels whose palette index is in `colours`, or of
+    /// every voxel if there is no filter.
+    pub fn cells(&self, colours: Option<&[u8]>) -> Vec<U3d> {
+        self.voxels.iter()
+            .filter(|&&(_, c)| colours.map_or(true, |colours| colours.contains(&c)))
+            .map(|&(p, _)| p)
+            .collect()
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn u32_at(data: &[u8], at: usize) -> io::Result<u32> {
+    match data.get(at..at + 4) {
+        Some(b) => Ok(b.iter().rev().fold(0, |v, &b| v << 8 | b as u32)),
+        None => Err(invalid_data(String::from("unexpected end of file"))),
+    }
+}
+
+/// Reads the first model of a `.vox` file.
+pub fn read<R: Read>(mut reader: R) -> io::Result<Vox> {
+    let mut data = Vec::new();
+    try!(reader.read_to_end(&mut data));
+    if data.len() < 8 || &data[..4] != MAGIC {
+        return Err(invalid_data(String::from("not a MagicaVoxel file")));
+    }
+    if &data[8..data.len().min(12)] != b"MAIN" {
+        return Err(invalid_data(String::from("missing MAIN chunk")));
+    }
+    let main_content = try!(u32_at(&data, 12)) as usize;
+    let main_children = try!(u32_at(&data, 16)) as usize;
+    let end = (20 + main_content).saturating_add(main_children).min(data.len());
+
+    let mut size = None;
+    let mut at = 20 + main_content;
+    while at < end {
+        let id = &data[at..(at + 4).min(end)];
+        let content = try!(u32_at(&data, at + 4)) as usize;
+        let children = try!(u32_at(&data, at + 8)) as usize;
+        let start = at + 12;
+        if start.saturating_add(content) > data.len() {
+            return Err(invalid_data(format!("{} chunk runs past the end of the file",
+                                            String::from_utf8_lossy(id))));
+        }
+        match id {
+            b"SIZE" if size.is_none() => {
+                size = Some((try!(u32_at(&data, start)) as usize,
+                             try!(u32_at(&data, start + 4)) as usize,
+                             try!(u32_at(&data, start + 8)) as usize));
+            },
+            b"XYZI" => {
+                let (xs, ys, zs) = try!(size.ok_or(invalid_data(String::from("XYZI chunk before SIZE"))));
+                let n = try!(u32_at(&data, start)) as usize;
+                if n.saturating_mul(4).saturating_add(4) > content {
+                    return Err(invalid_data(format!("XYZI chunk too short for {} voxels", n)));
+                }
+                let bytes = &data[start + 4..start + 4 + n * 4];
+                let mut voxels = Vec::with_capacity(n);
+                for v in bytes.chunks(4) {
+                    let (x, y, z) = (v[0] as usize, v[1] as usize, v[2] as usize);
+                    if x >= xs || y >= ys || z >= zs {
+                        return Err(invalid_data(format!("voxel {},{},{} beyond the {}x{}x{} model",
+                                                        x, y, z, xs, ys, zs)));
+                    }
+                    // Index 0 is empty space.
+                    if v[3] != 0 {
+                        voxels.push(((x, y, z), v[3]));
+                    }
+                }
+                return Ok(Vox { size: (xs, ys, zs), voxels: voxels });
+            },
+            _ => {},
+        }
+        at = start.saturating_add(content).saturating_add(children);
+    }
+    Err(invalid_data(String::from("no model in file")))
+}
+
+fn write_u32<W: Write>(writer: &mut W, v: u32) -> io::Result<()> {
+    writer.write_all(&[v as u8, (v >> 8) as u8, (v >> 16) as u8, (v >> 24) as u8])
+}
+
+fn write_chunk<W: Write>(writer: &mut W, id: &[u8; 4], content: &[u32], children: u32) -> io::Result<()> {
+    try!(writer.write_all(id));
+    try!(write_u32(writer, content.len() as u32 * 4));
+    try!(write_u32(writer, children));
+    for &v in content {
+        try!(write_u32(writer, v));
+    }
+    Ok(())
+}
+
+/// Writes a single model of the given size; voxels are `(x, y, z)` inside
+/// it.
+fn write_model<I, W>(size: U3d, voxels: I, colour: u8, mut writer: W) -> io::Result<()>
+    where I: Iterator<Item = U3d>, W: Write
+{
+    let (xs, ys, zs) = size;
+    if xs > MAX_SIZE || ys > MAX_SIZE || zs > MAX_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!(
+            "{}x{}x{} is larger than MagicaVoxel's {} cells a side", xs, ys, zs, MAX_SIZE)));
+    }
+    let mut xyzi = vec![0];
+    xyzi.extend(voxels.map(|(x, y, z)| x as u32 | (y as u32) << 8 | (z as u32) << 16 | (colour as u32) << 24));
+    xyzi[0] = xyzi.len() as u32 - 1;
+
+    try!(writer.write_all(MAGIC));
+    try!(write_u32(&mut writer, VERSION));
+    // MAIN has no contents of its own, only the SIZE and XYZI chunks.
+    try!(write_chunk(&mut writer, b"MAIN", &[], (12 + 12) + (12 + 4 * xyzi.len() as u32)));
+    try!(write_chunk(&mut writer, b"SIZE", &[xs as u32, ys as u32, zs as u32], 0));
+    write_chunk(&mut writer, b"XYZI", &xyzi, 0)
+}
+
+/// Writes the whole of `state` as a model of the same size, every live cell
+/// in palette index `colour`. Fails for worlds over `MAX_SIZE` a side.
+pub fn write<W: Write>(state: &State, colour: u8, writer: W) -> io::Result<()> {
+    write_model(state.dim(), state.live_cells(), colour, writer)
+}
+
+/// Writes the bounding box of the live cells of any world. Fails if it is
+/// over `MAX_SIZE` a side.
+pub fn write_world<V: World + ?Sized, W: Write>(world: &V, colour: u8, writer: W) -> io::Result<()> {
+    let cells: Vec<I3d> = world.live_cells().collect();
+    let low = cells.iter().fold(None, |low: Option<I3d>, &(x, y, z)| Some(match low {
+        None => (x, y, z),
+        Some((lx, ly, lz)) => (lx.min(x), ly.min(y), lz.min(z)),
+    }));
+    let (lx, ly, lz) = low.unwrap_or((0, 0, 0));
+    let size = cells.iter().fold((1, 1, 1), |(xs, ys, zs), &(x, y, z)| (
+        xs.max((x - lx) as usize + 1),
+        ys.max((y - ly) as usize + 1),
+        zs.max((z - lz) as usize + 1),
+    ));
+    let voxels = cells.iter().map(|&(x, y, z)| ((x - lx) as usize, (y - ly) as usize, (z - lz) as usize));
+    write_model(size, voxels, colour, writer)
+}
+
+/// Sets the voxels whose palette index is in `colours`, or all of them,
+/// alive with the model centred in the world. Fails without touching
+/// `state` if the model does not fit.
+pub fn load<R: Read>(state: &mut State, reader: R, colours: Option<&[u8]>) -> io::Result<Vox> {
+    let vox = try!(read(reader));
+    let (xs, ys, zs) = state.dim();
+    let (mx, my, mz) = vox.size;
+    if mx > xs || my > ys || mz > zs {
+        return Err(invalid_data(format!("the {}x{}x{} model does not fit in the {}x{}x{} world",
+                                        mx, my, mz, xs, ys, zs)));
+    }
+    let (cx, cy, cz) = ((xs - mx) / 2, (ys - my) / 2, (zs - mz) / 2);
+    for (x, y, z) in vox.cells(colours) {
+        state.set((cx + x, cy + y, cz + z), true);
+    }
+    Ok(vox)
+}
@@ -0,0 +1,632 @@
+//! The window: a cube per cell, keyboard controls, and the console and
+//! control requests served between frames.
+
+use glium::{self, Surface, glutin};
+use nalgebra::{self, Vec3};
+use glium::backend::glutin_backend::GlutinFacade;
+use glium::glutin::{Event, ElementState, VirtualKeyCode};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use clock_ticks;
+use game_of_life::{State, World, Soup, CycleDetector, History, Mesh, i2p, vox};
+use game_of_life::batch::OnSettle;
+use game_of_life::console::{self, Command, Console};
+use game_of_life::server::{Request, Server};
+use game_of_life::json::Json;
+use game_of_life::stats::{Tracker, StatsWriter};
+use error::StartupError;
+use {camera, cli, objects, script, soup, stats_writer, support};
+
+/// Most generations a single command or control request may step, as the
+/// window and every other client wait while they run.
+const MAX_STEPS: u64 = 100;
+
+/// Refuses to step more than `MAX_STEPS` generations at once.
+fn check_steps(n: u64) -> Result<(), String> {
+    if n > MAX_STEPS {
+        Err(format!("cannot step {} generations at once in the window, at most {}", n, MAX_STEPS))
+    } else {
+        Ok(())
+    }
+}
+
+pub enum Action {
+    Stop,
+    Continue,
+}
+
+pub struct PerObjectState {
+    pos: Vec3<f32>,
+    scale_factor: f32,
+    show: bool,
+    color: Vec3<f32>,
+}
+
+impl PerObjectState {
+    pub fn to_attr(&self) -> PerObjectAttr {
+        PerObjectAttr {
+            pos: self.pos.as_array().clone(),
+            scale_factor: self.scale_factor,
+            color: [
+                self.color.x,
+                self.color.y,
+                self.color.z,
+                if self.show { 1.0 } else { 0.0 }
+            ],
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct PerObjectAttr {
+    pub pos: [f32; 3],
+    pub color: [f32; 4],
+    pub scale_factor: f32,
+}
+
+implement_vertex!(PerObjectAttr, pos, color, scale_factor);
+
+fn initial_transforms(state: &State) -> Vec<PerObjectState> {
+    let (xs, ys, zs) = state.dim();
+    state.cell_states().enumerate().map(|(i, alive)| {
+        let (x, y, z) = i2p((xs, ys, zs), i);
+        PerObjectState {
+            pos: Vec3::new(
+                (x as f32 - (xs - 1) as f32 / 2.0) * 15.0,
+                (y as f32 - (ys - 1) as f32 / 2.0) * 15.0,
+                (z as f32 - (zs - 1) as f32 / 2.0) * 15.0,
+            ),
+            scale_factor: 5.0,
+            show: alive,
+            color: Vec3::new(0.9, 0.9, 0.9),
+                // if rand::random() {
+                //     Vec3::new(0.3 * rand::random::<f32>(), 0.05, 0.1)
+                // } else {
+                //     Vec3::new(0.1, 0.05, 0.3 * rand::random::<f32>())
+                // },
+        }
+    }).collect()
+}
+
+fn up_to_actual_state(state: &State, transforms: &mut Vec<PerObjectState>) {
+    for (mut st, alive) in transforms.iter_mut().zip(state.cell_states()) {
+        st.show = alive;
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Vertex {
+    position: [f32; 2],
+}
+
+implement_vertex!(Vertex, position);
+
+struct Applicaton {
+    display: GlutinFacade,
+    main_group: objects::InstancedObjects<PerObjectAttr>,
+    main_program: glium::Program,
+    background_program: glium::Program,
+    background_vb: glium::VertexBuffer<Vertex>,
+    background_ib: glium::IndexBuffer<u16>,
+    camera: camera::PerspectiveCamera,
+    state: State,
+    step_interval: u64,
+    detector: CycleDetector,
+    on_settle: OnSettle,
+    soup: Soup,
+    paused: bool,
+    history: History,
+    /// Generation typed on the number keys, gone to on Return.
+    jump_target: Option<u64>,
+    snapshot_path: String,
+    merge_faces: bool,
+    /// Console commands read on another thread, until `quit` or the end of
+    /// the input.
+    commands: Option<Receiver<io::Result<String>>>,
+    console: Console,
+    server: Option<Server>,
+    /// The world changed since the instance buffer was last updated.
+    dirty: bool,
+    stats: Option<(Tracker, StatsWriter<BufWriter<File>>)>,
+    time_from_start: f32,
+    angle: f64,
+    r: f32,
+}
+
+impl Applicaton {
+    fn new(state: State, options: &cli::Options, stats: Option<StatsWriter<BufWriter<File>>>,
+           commands: Option<Receiver<io::Result<String>>>, server: Option<Server>)
+           -> Result<Applicaton, StartupError> {
+        use glium::DisplayBuild;
+
+        let (width, height) = options.window_size;
+        let display = try!(glutin::WindowBuilder::new()
+            .with_dimensions(width, height)
+            .with_depth_buffer(24)
+            .build_glium()
+            .map_err(|e| StartupError::Context(format!("cannot open the window: {}", e))));
+
+        let assets = support::Assets::new(options.asset_dir.as_ref().map(|d| &d[..]));
+        let object_group = try!(objects::InstancedObjects::new(
+            &display,
+            try!(assets.read_mesh(&display, "src/support/cube.obj")),
+            initial_transforms(&state).iter()
+                .map(|s| s.to_attr())
+                .collect()
+        ).map_err(|e| StartupError::context("the instance buffer", e)));
+
+        let program = try!(assets.program(&display, &support::MAIN));
+        let bg_program = try!(assets.program(&display, &support::PROC_TEX));
+
+        let background_vb = try!(glium::VertexBuffer::new(
+            &display,
+            &[
+                Vertex { position: [-1.0, -1.0]},
+                Vertex { position: [-1.0,  1.0]},
+                Vertex { position: [ 1.0,  1.0]},
+                Vertex { position: [ 1.0, -1.0]}
+            ]
+        ).map_err(|e| StartupError::context("the background vertex buffer", e)));
+
+        let background_ib = try!(glium::IndexBuffer::new(
+            &display, glium::index::PrimitiveType::TriangleStrip, &[1 as u16, 2, 0, 3]
+        ).map_err(|e| StartupError::context("the background index buffer", e)));
+
+        let r = 1500.0;
+        Ok(Applicaton {
+            display: display,
+            main_group: object_group,
+            main_program: program,
+            background_program: bg_program,
+            background_vb: background_vb,
+            background_ib: background_ib,
+            time_from_start: 0.0,
+            detector: CycleDetector::new(options.max_period),
+            on_settle: options.on_settle,
+            soup: soup(options, state.seed().unwrap_or(0)),
+            paused: false,
+            jump_target: None,
+            history: History::new(options.history),
+            snapshot_path: options.snapshot_path.clone(),
+            merge_faces: options.merge_faces,
+            commands: commands,
+            console: Console::new(),
+            server: server,
+            dirty: false,
+            stats: stats.map(|writer| (Tracker::new(), writer)),
+            state: state,
+            step_interval: options.step_interval,
+            angle: 0.0,
+            r: r,
+            camera: camera::PerspectiveCamera::new()
+                .with_fov(60)
+                .with_position(Vec3::new(0.0, 0.0, r))
+                .with_zfar(5000.0)
+                .with_view_dimensions(width, height),
+        })
+    }
+
+    fn main_loop(&mut self) {
+        let params = glium::DrawParameters {
+            depth_test: glium::DepthTest::IfLess,
+            depth_write: true,
+            .. Default::default()
+        };
+
+        self.record_stats();
+        self.history.record(&self.state);
+        let mut transforms = initial_transforms(&self.state);
+        let mut last_step_time = clock_ticks::precise_time_ms();
+        let mut last_up_time = last_step_time;
+        let mut last_frame_time = last_step_time;
+        let mut frames = 0;
+        'main_loop: loop {
+            frames += 1;
+
+            let current_time = clock_ticks::precise_time_ms();
+            let dt = current_time - last_step_time;
+            let frame_dt = current_time - last_frame_time;
+            last_frame_time = current_time;
+            self.time_from_start += frame_dt as f32 / 1000.0;
+
+            self.run_commands();
+            self.serve_requests();
+            if dt > self.step_interval && !self.paused {
+                self.step();
+                last_step_time = clock_ticks::precise_time_ms();
+            }
+            if self.dirty {
+                up_to_actual_state(&self.state, &mut transforms);
+                self.update_state_buffer(transforms.iter());
+                self.dirty = false;
+            }
+
+            self.redraw_scene(self.display.draw(), &params);
+
+            if let Action::Stop = self.process_events() {
+                break 'main_loop;
+            }
+            let up_dt = clock_ticks::precise_time_ms() - last_up_time;
+            if up_dt > 1000 {
+                println!("{ft}: {fps}", ft=(up_dt as f32 / frames as f32), fps=(frames as f32 / up_dt as f32 * 1000.0));
+                last_up_time = clock_ticks::precise_time_ms();
+                frames = 0;
+            }
+        }
+    }
+
+    /// Steps the world, or redoes a step undone before, and acts on it
+    /// settling.
+    fn step(&mut self) {
+        self.dirty = true;
+        if self.history.step_forward(&mut self.state) {
+            return;
+        }
+        self.state.step_forward();
+        self.history.record(&self.state);
+        self.record_stats();
+        if let Some(outcome) = self.detector.observe(&self.state) {
+            println!("{}", outcome);
+            match self.on_settle {
+                OnSettle::Continue => {},
+                OnSettle::Stop => self.paused = true,
+                OnSettle::Reseed => {
+                    self.soup = self.soup.next_seed();
+                    println!("reseeding with seed {}", self.soup.seed());
+                    self.state.clear();
+                    self.soup.fill(&mut self.state);
+                    self.history.forget();
+                    self.history.record(&self.state);
+                    self.detector.reset();
+                },
+            }
+        }
+    }
+
+    /// Restores an earlier or later generation from the history and pauses.
+    fn rewind<F: FnOnce(&mut History, &mut State) -> bool>(&mut self, f: F) {
+        self.paused = true;
+        if f(&mut self.history, &mut self.state) {
+            self.detector.reset();
+            self.dirty = true;
+            println!("generation {}", self.state.generation());
+        }
+    }
+
+    /// Goes to `generation` if the history still holds it.
+    fn jump_to(&mut self, generation: u64) {
+        self.rewind(|history, state| history.jump_to(state, generation));
+        if self.state.generation() != generation {
+            eprintln!("generation {} is not kept, only {} to {}",
+                     generation, self.history.oldest_generation(), self.history.newest_generation());
+        }
+    }
+
+    /// Carries out the console commands that arrived since the last frame.
+    fn run_commands(&mut self) {
+        loop {
+            let line = match self.commands.as_ref().map(|c| c.try_recv()) {
+                Some(Ok(Ok(line))) => line,
+                Some(Ok(Err(e))) => {
+                    eprintln!("cannot read commands: {}", e);
+                    break;
+                },
+                Some(Err(TryRecvError::Empty)) | None => return,
+                Some(Err(TryRecvError::Disconnected)) => break,
+            };
+            match console::parse_line(&line) {
+                Ok(None) => {},
+                Ok(Some(Command::Quit)) => break,
+                Ok(Some(command)) => self.run_command(&command),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        self.commands = None;
+    }
+
+    fn run_command(&mut self, command: &Command) {
+        match *command {
+            Command::Step(n) => {
+                if let Err(e) = check_steps(n) {
+                    eprintln!("{}", e);
+                    return;
+                }
+                for _ in 0..n {
+                    self.step();
+                }
+                println!("generation {}, population {}", self.state.generation(), self.state.population());
+            },
+            Command::Pause => self.paused = true,
+            Command::Run => self.paused = false,
+            Command::Jump(generation) => self.jump_to(generation),
+            _ => match self.console.execute(&mut self.state, command) {
+                Ok(reply) => {
+                    if !reply.is_empty() {
+                        println!("{}", reply);
+                    }
+                    if let Command::Set(..) | Command::Rule(_) | Command::Clear | Command::Load(..) = *command {
+                        self.history.record(&self.state);
+                        self.detector.reset();
+                        self.dirty = true;
+                    }
+                },
+                Err(e) => eprintln!("{}", e),
+            },
+        }
+    }
+
+    /// Answers the control requests that arrived since the last frame.
+    fn serve_requests(&mut self) {
+        while let Some(call) = self.server.as_ref().and_then(|s| s.poll()) {
+            let result = match call.request {
+                Request::Step(n) => check_steps(n).and_then(|_| {
+                    for _ in 0..n {
+                        self.step();
+                    }
+                    self.server.as_mut().unwrap().handle(&mut self.state, &Request::Step(0))
+                }),
+                Request::Pause | Request::Run => {
+                    self.paused = call.request == Request::Pause;
+                    Ok(vec![(String::from("paused"), Json::Bool(self.paused))])
+                },
+                ref request => {
+                    let result = self.server.as_mut().unwrap().handle(&mut self.state, request);
+                    if let Request::Set(..) | Request::Rule(Some(_)) = *request {
+                        self.history.record(&self.state);
+                        self.detector.reset();
+                        self.dirty = true;
+                    }
+                    result
+                },
+            };
+            call.answer(result);
+        }
+    }
+
+    fn save_snapshot(&self) {
+        let saved = File::create(&self.snapshot_path)
+            .and_then(|file| self.state.save_snapshot(BufWriter::new(file)));
+        match saved {
+            Ok(()) => println!("saved generation {} to {}", self.state.generation(), self.snapshot_path),
+            Err(e) => eprintln!("cannot save {}: {}", self.snapshot_path, e),
+        }
+    }
+
+    fn export_vox(&self) {
+        let path = format!("generation-{}.vox", self.state.generation());
+        let written = File::create(&path)
+            .and_then(|file| vox::write(&self.state, vox::DEFAULT_COLOUR, BufWriter::new(file)));
+        match written {
+            Ok(()) => println!("exported {}", path),
+            Err(e) => eprintln!("cannot export {}: {}", path, e),
+        }
+    }
+
+    fn export_mesh(&self) {
+        let path = format!("generation-{}.obj", self.state.generation());
+        let mesh = Mesh::from_world(&self.state, self.merge_faces);
+        match File::create(&path).and_then(|file| mesh.write_obj(BufWriter::new(file))) {
+            Ok(()) => println!("exported {} with {} faces", path, mesh.faces.len()),
+            Err(e) => eprintln!("cannot export {}: {}", path, e),
+        }
+    }
+
+    /// Replaces the world with the saved snapshot, which must be of the same
+    /// size as the cubes on screen are fixed.
+    fn restore_snapshot(&mut self) {
+        let loaded = File::open(&self.snapshot_path)
+            .and_then(|file| State::load_snapshot(BufReader::new(file)));
+        match loaded {
+            Ok(ref state) if state.dim() != self.state.dim() => {
+                let (xs, ys, zs) = state.dim();
+                eprintln!("cannot restore {}: the world is {}x{}x{}", self.snapshot_path, xs, ys, zs);
+            },
+            Ok(mut state) => {
+                state.set_threads(self.state.threads());
+                self.state = state;
+                self.history.forget();
+                self.history.record(&self.state);
+                self.detector.reset();
+                self.dirty = true;
+                println!("restored generation {} from {}", self.state.generation(), self.snapshot_path);
+            },
+            Err(e) => eprintln!("cannot restore {}: {}", self.snapshot_path, e),
+        }
+    }
+
+    fn record_stats(&mut self) {
+        if let Some((ref mut tracker, ref mut writer)) = self.stats {
+            let stats = tracker.record(&self.state);
+            if let Err(e) = writer.write(&stats).and_then(|_| writer.flush()) {
+                eprintln!("cannot write statistics: {}", e);
+            }
+        }
+    }
+
+    fn update_state_buffer<'a, I>(&mut self, storage_iter: I)
+        where I: Iterator<Item = &'a PerObjectState> {
+        self.main_group.update_per_instance_buffer(|ref mut m|
+            for (transf, mat) in storage_iter.zip(m.iter_mut()) {
+                *mat = transf.to_attr();
+            }
+        );
+    }
+
+    fn redraw_scene(&self, mut target: glium::Frame, params: &glium::DrawParameters) {
+        let (x_size, y_size) = target.get_dimensions();
+        let resolution = nalgebra::Vec2::new(x_size as f32, y_size as f32);
+        target.clear_color_and_depth((0.0, 0.0, 0.0, 0.0), 1.0);
+        target.draw(
+            &self.background_vb,
+            &self.background_ib,
+            &self.background_program,
+            &uniform!{
+                u_time: self.time_from_start,
+                u_resolution: resolution,
+            },
+            &glium::DrawParameters {
+                depth_test: glium::DepthTest::Overwrite,
+                depth_write: false,
+                .. Default::default()
+            }
+        ).unwrap();
+
+        target.draw(
+            self.main_group.get_vertices_data(),
+            self.main_group.get_indices_data(),
+            &self.main_program,
+            &uniform!{ mvp: self.camera.to_vp_array(), u_time: self.time_from_start, },
+            &params
+        ).unwrap();
+
+        target.finish().unwrap();
+    }
+
+    fn process_events(&mut self) -> Action {
+        let recalc_cam_pos = |cam: &mut camera::PerspectiveCamera, angle: f64, r: f32| {
+                let new_pos = Vec3::new(angle.to_radians().sin() as f32, 0.0, angle.to_radians().cos() as f32) * r;
+                cam.with_position_mut(new_pos)
+                    .with_rotation_mut(Vec3::new(0.0, -angle.to_radians() as f32, 0.0));
+        };
+
+        for event in self.display.poll_events() {
+            match event {
+                Event::Closed => return Action::Stop,
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Add)) => {
+                    self.step();
+                }
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Subtract)) => {
+                    self.rewind(|history, state| history.step_back(state));
+                }
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Home)) => {
+                    let oldest = self.history.oldest_generation();
+                    self.rewind(|history, state| history.jump_to(state, oldest));
+                }
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::End)) => {
+                    let newest = self.history.newest_generation();
+                    self.rewind(|history, state| history.jump_to(state, newest));
+                }
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(key)) if digit(key).is_some() => {
+                    let typed = self.jump_target.unwrap_or(0).saturating_mul(10).saturating_add(digit(key).unwrap());
+                    self.jump_target = Some(typed);
+                    println!("jump to generation {} (Return)", typed);
+                }
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Return)) => {
+                    if let Some(generation) = self.jump_target.take() {
+                        self.jump_to(generation);
+                    }
+                }
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Space)) => {
+                    self.paused = !self.paused;
+                }
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::F5)) => {
+                    self.save_snapshot();
+                }
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::F6)) => {
+                    self.export_vox();
+                }
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::F7)) => {
+                    self.export_mesh();
+                }
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::F9)) => {
+                    self.restore_snapshot();
+                }
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Up)) => {
+                    self.r -= 7.5;
+                    recalc_cam_pos(&mut self.camera, self.angle, self.r);
+                },
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Down)) => {
+                    self.r += 7.5;
+                    recalc_cam_pos(&mut self.camera, self.angle, self.r);
+                },
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::A)) => {
+                    self.camera.add_position(Vec3::new(-1.0, 0.0, 0.0));
+                },
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::D)) => {
+                    self.camera.add_position(Vec3::new(1.0, 0.0, 0.0));
+                },
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::W)) => {
+                    self.camera.add_position(Vec3::new(0.0, -1.0, 0.0));
+                },
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::S)) => {
+                    self.camera.add_position(Vec3::new(0.0, 1.0, 0.0));
+                },
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Left)) => {
+                    self.angle -= 1.0;
+                    recalc_cam_pos(&mut self.camera, self.angle, self.r);
+                },
+
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::Right)) => {
+                    self.angle += 1.0;
+                    recalc_cam_pos(&mut self.camera, self.angle, self.r);
+                },
+
+                Event::Resized(x, y) => {
+                    self.camera.with_view_dimensions_mut(x, y);
+                },
+                _ => {}
+            }
+        }
+        Action::Continue
+    }
+}
+
+/// The digit of a number-row key.
+fn digit(key: VirtualKeyCode) -> Option<u64> {
+    let keys = [
+        VirtualKeyCode::Key0, VirtualKeyCode::Key1, VirtualKeyCode::Key2, VirtualKeyCode::Key3, VirtualKeyCode::Key4,
+        VirtualKeyCode::Key5, VirtualKeyCode::Key6, VirtualKeyCode::Key7, VirtualKeyCode::Key8, VirtualKeyCode::Key9,
+    ];
+    keys.iter().position(|&k| k == key).map(|d| d as u64)
+}
+
+/// Opens the window on `state` and runs it until it is closed.
+pub fn run(state: State, options: &cli::Options) -> Result<(), StartupError> {
+    let stats = try!(stats_writer(options));
+    let commands = match options.script {
+        Some(ref path) => {
+            let reader = try!(script(path));
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || {
+                for line in reader.lines() {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            });
+            Some(receiver)
+        },
+        None => None,
+    };
+    let server = match options.control {
+        Some(ref address) => {
+            let server = try!(Server::start(address).map_err(|e| format!("cannot listen on {}: {}", address, e)));
+            println!("listening for control requests on {}", address);
+            Some(server)
+        },
+        None => None,
+    };
+    let mut sterek = try!(Applicaton::new(state, options, stats, commands, server));
+    sterek.main_loop();
+    Ok(())
+}
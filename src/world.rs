@@ -0,0 +1,61 @@
+//! Interface shared by the simulation backends.
+
+use packed;
+use rule::Rule;
+
+/// Signed cell coordinates, for worlds without fixed bounds.
+pub type I3d = (i64, i64, i64);
+
+/// Fails for B0 rules, whose births with no neighbours would fill all of an
+/// unbounded world.
+pub fn check_unbounded(rule: &Rule) -> Result<(), String> {
+    if rule.next_state(false, 0) {
+        Err(format!("{} gives birth with no neighbours, which an unbounded world cannot run", rule))
+    } else {
+        Ok(())
+    }
+}
+
+pub trait World {
+    fn rule(&self) -> &Rule;
+
+    fn set_rule(&mut self, rule: Rule);
+
+    /// Checks that the world can run `rule` before it is passed to
+    /// `set_rule`.
+    fn check_rule(&self, rule: &Rule) -> Result<(), String> {
+        let _ = rule;
+        Ok(())
+    }
+
+    /// Number of generations stepped since creation.
+    fn generation(&self) -> u64;
+
+    fn step(&mut self);
+
+    fn is_alive(&self, p: I3d) -> bool;
+
+    fn set(&mut self, p: I3d, alive: bool);
+
+    /// True if `p` lies inside the world; unbounded worlds contain every
+    /// cell.
+    fn contains(&self, p: I3d) -> bool {
+        let _ = p;
+        true
+    }
+
+    fn population(&self) -> usize;
+
+    fn live_cells<'a>(&'a self) -> Box<Iterator<Item = I3d> + 'a>;
+
+    /// True if the last step changed nothing.
+    fn is_static(&self) -> bool;
+
+    /// Hash of the live cells, equal for equal worlds of the same backend
+    /// whatever their generation.
+    fn state_hash(&self) -> u64 {
+        self.live_cells().fold(0, |h: u64, (x, y, z)| {
+            h.wrapping_add(packed::mix(packed::mix(packed::mix(x as u64) ^ y as u64) ^ z as u64))
+        })
+    }
+}
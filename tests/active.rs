@@ -0,0 +1,67 @@
+extern crate game_of_life;
+
+use game_of_life::{State, Rule, Soup, Boundary};
+
+fn world(tracking: bool, boundaries: [Boundary; 3]) -> State {
+    let mut state = State::new((30, 24, 70), Rule::parse("B5/S45").unwrap());
+    Soup::new(11).fill(&mut state);
+    state.set_boundaries(boundaries);
+    state.set_active_tracking(tracking);
+    state
+}
+
+#[test]
+fn tracking_gives_identical_results() {
+    for &b in [Boundary::Periodic, Boundary::Dead, Boundary::Alive, Boundary::Mirror].iter() {
+        let mut full = world(false, [b; 3]);
+        let mut tracked = world(true, [b; 3]);
+        for g in 0..20 {
+            if g == 7 {
+                for state in [&mut full, &mut tracked].iter_mut() {
+                    state.set((3, 5, 6), true);
+                    state.set((29, 0, 69), true);
+                }
+            }
+            if g == 12 {
+                full.set_rule(Rule::parse("B4/S45").unwrap());
+                tracked.set_rule(Rule::parse("B4/S45").unwrap());
+            }
+            full.step_forward();
+            tracked.step_forward();
+            assert!(tracked.words() == full.words(), "{:?} diverged at generation {}", b, g + 1);
+        }
+    }
+}
+
+#[test]
+fn tracking_with_threads() {
+    let mut full = world(false, [Boundary::Periodic; 3]);
+    let mut tracked = world(true, [Boundary::Periodic; 3]);
+    tracked.set_threads(4);
+    for _ in 0..10 {
+        full.step_forward();
+        tracked.step_forward();
+        assert!(tracked.words() == full.words());
+    }
+}
+
+#[test]
+fn settled_worlds_evaluate_fewer_cells() {
+    let mut state = State::new((32, 32, 32), Rule::parse("B5/S457").unwrap());
+    state.set_boundaries([Boundary::Dead; 3]);
+    // A 2x2x2 block, still under S7.
+    for &(x, y, z) in [(4, 4, 4), (4, 4, 5), (4, 5, 4), (4, 5, 5),
+                       (5, 4, 4), (5, 4, 5), (5, 5, 4), (5, 5, 5)].iter() {
+        state.set((x, y, z), true);
+    }
+    state.step_forward();
+    assert_eq!(state.evaluated_cells(), 32 * 32 * 32);
+    state.step_forward();
+    assert_eq!(state.evaluated_cells(), 0);
+    assert_eq!(state.population(), 8);
+
+    state.set((20, 20, 20), true);
+    state.step_forward();
+    assert_eq!(state.evaluated_cells(), 9 * 32);
+    assert!(!state.is_alive((20, 20, 20)));
+}
@@ -0,0 +1,62 @@
+extern crate game_of_life;
+
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read};
+
+use game_of_life::{State, Rule, Soup, Outcome, cell_list};
+use game_of_life::batch::{self, BatchConfig, OnSettle, StopReason};
+
+fn soup_world(seed: u64) -> State {
+    let mut state = State::new((12, 12, 12), Rule::parse("B5/S45").unwrap());
+    Soup::new(seed).fill(&mut state);
+    state
+}
+
+fn config(generations: u64, on_settle: OnSettle) -> BatchConfig {
+    BatchConfig { generations: generations, on_settle: on_settle, max_period: 8, record_stats: false }
+}
+
+#[test]
+fn runs_a_seeded_soup_and_writes_its_outputs() {
+    let mut expected = soup_world(7);
+    let mut populations = vec![expected.population()];
+    for _ in 0..15 {
+        expected.step_forward();
+        populations.push(expected.population());
+    }
+
+    let mut world = soup_world(7);
+    let result = batch::run(&mut world, &config(15, OnSettle::Continue), |_| ());
+    assert_eq!(result.reason, StopReason::Finished);
+    assert_eq!(world.generation(), 15);
+    assert_eq!(world.population(), expected.population());
+    assert_eq!(result.population, populations);
+
+    let mut series = Vec::new();
+    result.write_population(&mut series).unwrap();
+    let lines: Vec<String> = populations.iter().enumerate().map(|(g, p)| format!("{} {}", g, p)).collect();
+    assert_eq!(String::from_utf8(series).unwrap(), lines.join("\n") + "\n");
+
+    let path = env::temp_dir().join("game-of-life-batch-test.cells");
+    cell_list::write_world(&world, BufWriter::new(File::create(&path).unwrap())).unwrap();
+    let mut text = String::new();
+    File::open(&path).unwrap().read_to_string(&mut text).unwrap();
+    assert!(text.contains(" generation 15\n"), "{}", text);
+    let mut cells = cell_list::read_world(BufReader::new(File::open(&path).unwrap())).unwrap();
+    cells.sort();
+    let mut live: Vec<_> = expected.live_cells().map(|(x, y, z)| (x as i64, y as i64, z as i64)).collect();
+    live.sort();
+    assert_eq!(cells, live);
+}
+
+#[test]
+fn stops_once_the_world_dies_out() {
+    let mut world = State::new((8, 8, 8), Rule::parse("B5/S45").unwrap());
+    world.set((3, 3, 3), true);
+    let result = batch::run(&mut world, &config(100, OnSettle::Stop), |_| ());
+    assert_eq!(result.reason, StopReason::Settled(Outcome::Extinct { since: 1 }));
+    // Dying out is only known once the empty world is seen again.
+    assert_eq!(world.generation(), 2);
+    assert_eq!(result.population, vec![1, 0, 0]);
+}
@@ -0,0 +1,96 @@
+extern crate game_of_life;
+
+use std::env;
+use std::fs;
+
+use game_of_life::{Rule, SparseWorld, State, World};
+use game_of_life::console::{self, Command, Console};
+
+fn run<W: World + ?Sized>(console: &mut Console, world: &mut W, script: &str) -> Vec<String> {
+    script.lines()
+        .filter_map(|line| console::parse_line(line).unwrap())
+        .map(|command| console.execute(world, &command).unwrap())
+        .filter(|reply| !reply.is_empty())
+        .collect()
+}
+
+#[test]
+fn parses_commands() {
+    assert_eq!(console::parse_line("  # just a comment"), Ok(None));
+    assert_eq!(console::parse_line("step"), Ok(Some(Command::Step(1))));
+    assert_eq!(console::parse_line("step 10 # ten"), Ok(Some(Command::Step(10))));
+    assert_eq!(console::parse_line("set 3 -4 5 1"), Ok(Some(Command::Set((3, -4, 5), true))));
+    assert_eq!(console::parse_line("rule B5/S45"), Ok(Some(Command::Rule(Rule::parse("B5/S45").unwrap()))));
+    assert_eq!(console::parse_line("load a.rle 1 2 3"), Ok(Some(Command::Load(String::from("a.rle"), Some((1, 2, 3))))));
+    for bad in ["step ten", "set 1 2 3", "set 1 2 3 2", "rule B5/Sx", "clear all", "jump back", "jump 3 4"].iter() {
+        assert!(console::parse_line(bad).is_err(), "{}", bad);
+    }
+    for line in ["step 4", "set 1 2 -3 0", "load x.vox", "load x.rle 0 0 7", "save y.obj", "stats", "jump 12"].iter() {
+        assert_eq!(&console::parse_line(line).unwrap().unwrap().to_string(), line);
+    }
+}
+
+#[test]
+fn drives_a_world() {
+    let mut state = State::new((10, 10, 10), Rule::parse("B5/S45").unwrap());
+    let mut console = Console::new();
+    let replies = run(&mut console, &mut state, "
+        rule B6/S567
+        # the Life blinker in two layers
+        set 4 3 4 1
+        set 4 3 5 1
+        set 4 4 4 1
+        set 4 4 5 1
+        set 4 5 4 1
+        set 4 5 5 1
+        stats
+        step 3
+        stats
+    ");
+    assert_eq!(*state.rule(), Rule::parse("B6/S567").unwrap());
+    assert_eq!(state.generation(), 3);
+    assert_eq!(replies.len(), 3);
+    assert!(replies[0].starts_with("{\"generation\":0,\"population\":6,"));
+    assert_eq!(replies[1], "generation 3, population 6");
+    assert!(replies[2].contains("\"births\":4,\"deaths\":4,"), "{}", replies[2]);
+
+    assert!(console.execute(&mut state, &Command::Set((10, 0, 0), true)).is_err());
+    let mut slab = State::new((10, 10, 1), Rule::parse("B3/S23").unwrap());
+    assert!(console.execute(&mut slab, &Command::Rule(Rule::parse("B9/S2").unwrap())).is_err());
+    assert_eq!(*slab.rule(), Rule::parse("B3/S23").unwrap());
+    assert!(console.execute(&mut state, &Command::Jump(1)).is_err());
+    run(&mut console, &mut state, "clear");
+    assert_eq!(state.population(), 0);
+}
+
+#[test]
+fn saves_and_loads_files() {
+    let dir = env::temp_dir();
+    let path = |name: &str| dir.join(format!("console-{}", name)).to_str().unwrap().to_string();
+    let mut world = SparseWorld::new(Rule::parse("B5/S45").unwrap());
+    let mut console = Console::new();
+    run(&mut console, &mut world, "set -3 7 100 1\nset -2 7 100 1\nset -2 8 101 1");
+
+    for name in ["cells.txt", "pattern.rle", "model.vox"].iter() {
+        let file = path(name);
+        run(&mut console, &mut world, &format!("save {}", file));
+        let mut copy = SparseWorld::new(Rule::parse("B3/S23").unwrap());
+        assert_eq!(run(&mut console, &mut copy, &format!("load {} -3 7 100", file)), vec!["loaded 3 cells"]);
+        let mut cells: Vec<_> = copy.live_cells().collect();
+        cells.sort();
+        assert_eq!(cells, vec![(-3, 7, 100), (-2, 7, 100), (-2, 8, 101)], "{}", name);
+        if name.ends_with(".rle") {
+            assert_eq!(*copy.rule(), Rule::parse("B5/S45").unwrap());
+        }
+        fs::remove_file(file).unwrap();
+    }
+
+    // Cells placed outside a bounded world leave it untouched.
+    let file = path("far.txt");
+    run(&mut console, &mut world, &format!("save {}", file));
+    let mut state = State::new((5, 5, 5), Rule::parse("B5/S45").unwrap());
+    state.set((1, 1, 1), true);
+    assert!(console.execute(&mut state, &Command::Load(file.clone(), None)).is_err());
+    assert_eq!(state.population(), 1);
+    fs::remove_file(file).unwrap();
+}
@@ -0,0 +1,101 @@
+extern crate game_of_life;
+
+use game_of_life::{State, Rule, Soup, Boundary, SparseWorld, World, CycleDetector, Outcome};
+use game_of_life::batch::{self, BatchConfig, OnSettle, StopReason};
+
+/// 2D Life on a dead-walled slab.
+fn life(cells: &[(usize, usize)]) -> State {
+    let mut state = State::new((10, 10, 1), Rule::parse("B3/S23").unwrap());
+    state.set_boundaries([Boundary::Dead; 3]);
+    for &(x, y) in cells {
+        state.set((x, y, 0), true);
+    }
+    state
+}
+
+fn settle<W: World>(world: &mut W, steps: usize) -> Option<Outcome> {
+    let mut detector = CycleDetector::default();
+    detector.observe(world);
+    for _ in 0..steps {
+        world.step();
+        if let Some(outcome) = detector.observe(world) {
+            return Some(outcome);
+        }
+    }
+    None
+}
+
+#[test]
+fn still_life_is_static() {
+    let mut block = life(&[(2, 2), (2, 3), (3, 2), (3, 3)]);
+    assert_eq!(settle(&mut block, 10), Some(Outcome::Static { since: 0 }));
+}
+
+#[test]
+fn blinker_has_period_two() {
+    let mut blinker = life(&[(4, 3), (4, 4), (4, 5)]);
+    assert_eq!(settle(&mut blinker, 10), Some(Outcome::Periodic { period: 2, since: 0 }));
+    assert_eq!(format!("{}", Outcome::Periodic { period: 2, since: 0 }),
+               "periodic with period 2 since generation 0");
+}
+
+#[test]
+fn dying_pattern_is_extinct() {
+    // Two cells die at once; the empty world repeats from generation 1.
+    let mut pair = life(&[(1, 1), (1, 2)]);
+    assert_eq!(settle(&mut pair, 10), Some(Outcome::Extinct { since: 1 }));
+}
+
+#[test]
+fn cycles_entered_late_are_dated() {
+    // A pre-block: three cells of a square, filled in at generation 1.
+    let mut pre_block = life(&[(2, 2), (2, 3), (3, 2)]);
+    assert_eq!(settle(&mut pre_block, 10), Some(Outcome::Static { since: 1 }));
+}
+
+#[test]
+fn unbounded_worlds_settle_too() {
+    // A 2x2x2 block straddling the origin, still under S7.
+    let mut sparse = SparseWorld::new(Rule::parse("B5/S457").unwrap());
+    for x in -1..1 {
+        for y in -1..1 {
+            for z in -1..1 {
+                sparse.set((x, y, z), true);
+            }
+        }
+    }
+    assert_eq!(settle(&mut sparse, 10), Some(Outcome::Static { since: 0 }));
+}
+
+#[test]
+fn periods_beyond_the_history_are_missed() {
+    let mut blinker = life(&[(4, 3), (4, 4), (4, 5)]);
+    let mut detector = CycleDetector::new(1);
+    detector.observe(&blinker);
+    for _ in 0..10 {
+        blinker.step_forward();
+        assert_eq!(detector.observe(&blinker), None);
+    }
+}
+
+#[test]
+fn batch_stops_or_reseeds() {
+    let config = |on_settle| BatchConfig { generations: 100, on_settle: on_settle, max_period: 8, record_stats: false };
+
+    let mut block = life(&[(2, 2), (2, 3), (3, 2), (3, 3)]);
+    let result = batch::run(&mut block, &config(OnSettle::Stop), |_| ());
+    assert_eq!(result.reason, StopReason::Settled(Outcome::Static { since: 0 }));
+    assert_eq!(block.generation(), 1);
+
+    let mut block = life(&[(2, 2), (2, 3), (3, 2), (3, 3)]);
+    let mut reseeds = 0;
+    let result = batch::run(&mut block, &config(OnSettle::Reseed), |world| {
+        reseeds += 1;
+        world.clear();
+        Soup::new(reseeds).with_region(game_of_life::Region::Whole).fill(world);
+    });
+    assert_eq!(result.reason, StopReason::Finished);
+    assert_eq!(block.generation(), 100);
+    assert_eq!(result.settled.len() as u64, reseeds);
+    assert!(reseeds >= 1);
+}
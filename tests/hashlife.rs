@@ -0,0 +1,129 @@
+extern crate game_of_life;
+
+use game_of_life::{State, SparseWorld, HashLife, World, Rule, Soup, Region};
+use game_of_life::console::{Command, Console};
+use game_of_life::hashlife::MAX_STEP_EXPONENT;
+
+fn soup<W: World>(world: &mut W, seed: u64, offset: i64) {
+    let mut state = State::new((10, 10, 10), *world.rule());
+    Soup::new(seed).with_region(Region::Whole).with_lattice_skip(None).with_density(0.4).fill(&mut state);
+    for (x, y, z) in World::live_cells(&state) {
+        world.set((x + offset, y + offset, z + offset), true);
+    }
+}
+
+fn sorted<W: World>(world: &W) -> Vec<(i64, i64, i64)> {
+    let mut cells: Vec<_> = world.live_cells().collect();
+    cells.sort();
+    cells
+}
+
+#[test]
+fn single_steps_match_sparse_world() {
+    for &(rule, offset) in [("B5/S45", 0), ("B4/S3..6", -5), ("B5,6/S4..7", 3)].iter() {
+        let rule = Rule::parse(rule).unwrap();
+        let mut sparse = SparseWorld::new(rule);
+        let mut hash = HashLife::new(rule);
+        soup(&mut sparse, 4, offset);
+        soup(&mut hash, 4, offset);
+        for g in 0..12 {
+            assert!(sorted(&sparse) == sorted(&hash), "{} generation {}", rule, g);
+            assert_eq!(sparse.population(), hash.population());
+            sparse.step();
+            hash.step();
+        }
+    }
+}
+
+#[test]
+fn power_of_two_steps_match_sparse_world() {
+    let rule = Rule::parse("B5/S45").unwrap();
+    let mut sparse = SparseWorld::new(rule);
+    let mut hash = HashLife::new(rule);
+    soup(&mut sparse, 8, -4);
+    soup(&mut hash, 8, -4);
+    for k in 0..5 {
+        for _ in 0..1 << k {
+            sparse.step();
+        }
+        hash.step_pow2(k);
+        assert_eq!(hash.generation(), sparse.generation());
+        assert!(sorted(&sparse) == sorted(&hash), "after 2^{} step", k);
+    }
+}
+
+#[test]
+fn garbage_collection_keeps_the_pattern() {
+    let rule = Rule::parse("B4/S3..6").unwrap();
+    let mut hash = HashLife::new(rule);
+    soup(&mut hash, 2, 0);
+    hash.step_pow2(3);
+    let before = sorted(&hash);
+    hash.collect_garbage();
+    assert!(sorted(&hash) == before);
+    let mut again = HashLife::new(rule);
+    soup(&mut again, 2, 0);
+    hash.step_pow2(2);
+    again.step_pow2(3);
+    again.step_pow2(2);
+    assert!(sorted(&hash) == sorted(&again));
+}
+
+#[test]
+fn still_life_is_static() {
+    // A 2x2x2 block has 7 neighbours per cell.
+    let mut hash = HashLife::new(Rule::new(&[], &[7]));
+    for x in 0..2 {
+        for y in 0..2 {
+            for z in 0..2 {
+                hash.set((x, y, z), true);
+            }
+        }
+    }
+    hash.step_pow2(4);
+    assert!(hash.is_static());
+    assert_eq!(hash.population(), 8);
+    assert_eq!(hash.generation(), 16);
+}
+
+#[test]
+fn refuses_b0_rules() {
+    let mut hash = HashLife::new(Rule::default());
+    let b0 = Rule::parse("B0,5/S4").unwrap();
+    assert!(hash.check_rule(&b0).is_err());
+    let mut console = Console::new();
+    assert!(console.execute(&mut hash, &Command::Rule(b0)).is_err());
+    assert_eq!(*hash.rule(), Rule::default());
+}
+
+#[test]
+fn longest_steps_stay_addressable() {
+    // A 2x2x2 block, still under S7.
+    let mut hash = HashLife::new(Rule::parse("B5/S457").unwrap());
+    for &p in [(0, 0, 0), (1, 0, 0), (0, 1, 0), (0, 0, 1), (1, 1, 0), (1, 0, 1), (0, 1, 1), (1, 1, 1)].iter() {
+        hash.set(p, true);
+    }
+    hash.set_step_exponent(MAX_STEP_EXPONENT);
+    hash.step();
+    assert_eq!(hash.generation(), 1 << MAX_STEP_EXPONENT);
+    assert_eq!(sorted(&hash).len(), 8);
+    assert!(hash.is_alive((1, 1, 1)));
+}
+
+#[test]
+#[should_panic]
+fn refuses_longer_steps() {
+    HashLife::new(Rule::default()).set_step_exponent(MAX_STEP_EXPONENT + 1);
+}
+
+#[test]
+#[should_panic]
+fn refuses_longer_single_steps() {
+    HashLife::new(Rule::default()).step_pow2(64);
+}
+
+#[test]
+#[should_panic]
+fn refuses_cells_beyond_the_largest_root() {
+    HashLife::new(Rule::default()).set((1 << 62, 0, 0), true);
+}
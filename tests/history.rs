@@ -0,0 +1,134 @@
+extern crate game_of_life;
+
+use game_of_life::{State, Rule, Soup, History};
+
+fn world() -> State {
+    let mut state = State::new((20, 16, 70), Rule::default());
+    Soup::new(5).fill(&mut state);
+    state
+}
+
+/// Steps `state` `n` times, recording every generation.
+fn run(state: &mut State, history: &mut History, n: usize) -> Vec<Vec<u64>> {
+    history.record(state);
+    let mut words = vec![state.words().to_vec()];
+    for _ in 0..n {
+        state.step_forward();
+        history.record(state);
+        words.push(state.words().to_vec());
+    }
+    words
+}
+
+#[test]
+fn steps_back_and_forward_through_recorded_generations() {
+    let mut state = world();
+    let mut history = History::new(100);
+    let words = run(&mut state, &mut history, 10);
+    for g in (0..10).rev() {
+        assert!(history.step_back(&mut state));
+        assert_eq!(state.generation(), g as u64);
+        assert!(state.words() == &words[g][..]);
+    }
+    assert!(!history.step_back(&mut state));
+    for g in 1..11 {
+        assert!(history.step_forward(&mut state));
+        assert!(state.words() == &words[g][..]);
+    }
+    assert!(!history.step_forward(&mut state));
+}
+
+#[test]
+fn jumps_to_any_kept_generation() {
+    let mut state = world();
+    let mut history = History::new(100);
+    let words = run(&mut state, &mut history, 12);
+    assert!(history.jump_to(&mut state, 3));
+    assert!(state.words() == &words[3][..]);
+    assert_eq!(history.newest_generation(), 12);
+    assert!(history.jump_to(&mut state, 9));
+    assert!(state.words() == &words[9][..]);
+    assert!(!history.jump_to(&mut state, 13));
+    assert_eq!(state.generation(), 9);
+}
+
+#[test]
+fn stepping_after_a_rewind_matches_the_original_run() {
+    let mut state = world();
+    let mut history = History::new(100);
+    let words = run(&mut state, &mut history, 8);
+    assert!(history.jump_to(&mut state, 2));
+    for g in 3..9 {
+        state.step_forward();
+        history.record(&state);
+        assert!(state.words() == &words[g][..]);
+    }
+}
+
+#[test]
+fn edits_branch_off() {
+    let mut state = world();
+    let mut history = History::new(100);
+    let words = run(&mut state, &mut history, 6);
+    assert!(history.jump_to(&mut state, 4));
+    state.clear();
+    state.set((1, 2, 3), true);
+    history.record(&state);
+    assert_eq!(history.newest_generation(), 4);
+    assert!(!history.step_forward(&mut state));
+
+    // The edit is a state of its own; going back first restores generation 4
+    // as it was before it.
+    assert!(history.step_back(&mut state));
+    assert_eq!(state.generation(), 4);
+    assert!(state.words() == &words[4][..]);
+    assert!(history.step_forward(&mut state));
+    assert_eq!(state.population(), 1);
+}
+
+#[test]
+fn unrecorded_changes_are_kept_when_stepping_back() {
+    let mut state = world();
+    let mut history = History::new(100);
+    run(&mut state, &mut history, 3);
+    state.step_forward();
+    let latest = state.words().to_vec();
+    assert!(history.step_back(&mut state));
+    assert_eq!(state.generation(), 3);
+    assert!(history.step_forward(&mut state));
+    assert!(state.words() == &latest[..]);
+}
+
+#[test]
+fn history_is_bounded() {
+    let mut state = world();
+    let mut history = History::new(5);
+    let words = run(&mut state, &mut history, 20);
+    assert_eq!(history.len(), 5);
+    assert_eq!(history.oldest_generation(), 15);
+    assert!(!history.jump_to(&mut state, 14));
+    assert!(history.jump_to(&mut state, 15));
+    assert!(state.words() == &words[15][..]);
+    assert!(history.stored_words() < 5 * state.words().len());
+}
+
+#[test]
+fn an_older_state_starts_afresh() {
+    let mut state = world();
+    let mut history = History::new(100);
+    let words = run(&mut state, &mut history, 6);
+    let mut restored = world();
+    restored.set_generation(2);
+    restored.set((0, 0, 0), true);
+    history.record(&restored);
+    assert_eq!((history.oldest_generation(), history.newest_generation()), (2, 2));
+    assert!(history.is_empty());
+    assert!(!history.jump_to(&mut restored, 5));
+
+    let mut state = restored;
+    run(&mut state, &mut history, 3);
+    assert_eq!((history.oldest_generation(), history.newest_generation()), (2, 5));
+    assert!(history.jump_to(&mut state, 2));
+    assert!(state.is_alive((0, 0, 0)));
+    assert!(state.words() != &words[2][..]);
+}
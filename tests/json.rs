@@ -0,0 +1,34 @@
+extern crate game_of_life;
+
+use game_of_life::json::Json;
+
+#[test]
+fn parses_and_prints_values() {
+    let value = Json::parse(r#" {"command": "set", "cells": [[1, -2, 3e1]], "alive": false,
+                                 "note": "tab\there \"quoted\" \u00e9", "id": null} "#).unwrap();
+    assert_eq!(value.get("command").and_then(|c| c.as_str()), Some("set"));
+    assert_eq!(value.get("alive").and_then(|a| a.as_bool()), Some(false));
+    let cell = &value.get("cells").unwrap().as_array().unwrap()[0];
+    assert_eq!(cell.as_array().unwrap().iter().map(|c| c.as_i64()).collect::<Vec<_>>(),
+               vec![Some(1), Some(-2), Some(30)]);
+    assert_eq!(value.get("note").and_then(|n| n.as_str()), Some("tab\there \"quoted\" \u{e9}"));
+    assert_eq!(value.get("id"), Some(&Json::Null));
+    assert_eq!(Json::parse(&value.to_string()), Ok(value));
+    assert_eq!(Json::Number(2.5).as_i64(), None);
+}
+
+#[test]
+fn rejects_malformed_input() {
+    for bad in ["", "{", "[1,]", "{\"a\" 1}", "{1: 2}", "tru", "\"open", "1 2", "\"\\u12\""].iter() {
+        assert!(Json::parse(bad).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn limits_nesting() {
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(Json::parse(&nested(100)).is_ok());
+    let error = Json::parse(&nested(200000)).unwrap_err();
+    assert!(error.ends_with("nested too deeply"), "{}", error);
+    assert!(Json::parse(&format!("{}1", "{\"a\":".repeat(200000))).is_err());
+}
@@ -0,0 +1,153 @@
+extern crate game_of_life;
+
+use std::collections::HashMap;
+
+use game_of_life::{Mesh, Rule, SparseWorld, State, Soup, World};
+use game_of_life::mesh::Format;
+
+fn world(cells: &[(i64, i64, i64)]) -> SparseWorld {
+    let mut world = SparseWorld::new(Rule::parse("B5/S45").unwrap());
+    for &p in cells {
+        world.set(p, true);
+    }
+    world
+}
+
+/// Number of faces going along every directed edge.
+fn edges(mesh: &Mesh) -> HashMap<(u32, u32), usize> {
+    let mut edges = HashMap::new();
+    for face in mesh.faces.iter() {
+        for i in 0..face.len() {
+            *edges.entry((face[i], face[(i + 1) % face.len()])).or_insert(0) += 1;
+        }
+    }
+    edges
+}
+
+/// Every edge of a closed, consistently oriented mesh is used once in each
+/// direction.
+fn assert_closed(mesh: &Mesh) {
+    let edges = edges(mesh);
+    for (&(a, b), &n) in edges.iter() {
+        assert_eq!(edges.get(&(b, a)), Some(&n), "edge {} -> {}", a, b);
+    }
+}
+
+/// Twice the area of every face, which are axis-aligned.
+fn area(mesh: &Mesh) -> i64 {
+    mesh.faces.iter().map(|face| {
+        let v = |k: usize| mesh.vertices[face[k % face.len()] as usize];
+        let n = (0..face.len()).fold((0, 0, 0), |n, k| {
+            let (a, b) = (v(k), v(k + 1));
+            (n.0 + a.1 * b.2 - a.2 * b.1, n.1 + a.2 * b.0 - a.0 * b.2, n.2 + a.0 * b.1 - a.1 * b.0)
+        });
+        n.0.abs() + n.1.abs() + n.2.abs()
+    }).sum()
+}
+
+#[test]
+fn keeps_only_exposed_faces() {
+    let mesh = Mesh::from_world(&world(&[(0, 0, 0), (1, 0, 0)]), false);
+    assert_eq!(mesh.faces.len(), 10);
+    assert_eq!(mesh.vertices.len(), 12);
+    assert_closed(&mesh);
+}
+
+#[test]
+fn faces_point_outwards() {
+    let mesh = Mesh::from_world(&world(&[(3, -2, 7)]), false);
+    for q in mesh.faces.iter() {
+        let n = mesh.normal(q);
+        let centre = q.iter().fold((0, 0, 0), |c, &i| {
+            let v = mesh.vertices[i as usize];
+            (c.0 + v.0, c.1 + v.1, c.2 + v.2)
+        });
+        // Centre of the face relative to the centre of the cube, times 4.
+        let out = (centre.0 - 14, centre.1 + 6, centre.2 - 30);
+        assert_eq!(out, (n.0 * 2, n.1 * 2, n.2 * 2));
+    }
+}
+
+#[test]
+fn merges_coplanar_faces() {
+    let mut cells = Vec::new();
+    for x in 0..3 {
+        for y in 0..4 {
+            for z in 0..5 {
+                cells.push((x, y, z));
+            }
+        }
+    }
+    let box_world = world(&cells);
+    assert_eq!(Mesh::from_world(&box_world, false).faces.len(), 2 * (12 + 15 + 20));
+    assert_eq!(Mesh::from_world(&box_world, true).faces.len(), 6);
+
+    let mut state = State::new((12, 12, 12), Rule::parse("B5/S45").unwrap());
+    Soup::new(3).fill(&mut state);
+    let faces = Mesh::from_world(&state, false);
+    let merged = Mesh::from_world(&state, true);
+    assert_closed(&faces);
+    assert_closed(&merged);
+    assert!(merged.faces.len() < faces.faces.len());
+    assert_eq!(area(&merged), area(&faces));
+}
+
+#[test]
+fn merged_meshes_are_watertight() {
+    // Stacked slabs of shrinking size: merged faces around each step have
+    // corners of their neighbours in the middle of their edges.
+    let mut cells = Vec::new();
+    for &(z, size) in [(0, 6), (1, 4), (2, 3), (3, 1)].iter() {
+        for x in 0..size {
+            for y in 0..size + 1 {
+                cells.push((x, y, z));
+            }
+        }
+    }
+    let stairs = world(&cells);
+    let merged = Mesh::from_world(&stairs, true);
+    assert!(merged.faces.len() < Mesh::from_world(&stairs, false).faces.len());
+    assert!(merged.faces.iter().any(|f| f.len() > 4));
+    assert_closed(&merged);
+    let mut shared = HashMap::new();
+    for (&(a, b), &n) in edges(&merged).iter() {
+        *shared.entry((a.min(b), a.max(b))).or_insert(0) += n;
+    }
+    assert!(shared.values().all(|&n| n == 2), "{:?}", shared);
+    assert_eq!(area(&merged), area(&Mesh::from_world(&stairs, false)));
+    for face in merged.faces.iter() {
+        assert!(merged.normal(face) != (0, 0, 0));
+    }
+
+    let mut stl = Vec::new();
+    merged.write(Format::Stl, &mut stl).unwrap();
+    let triangles = merged.faces.iter().map(|f| if f.len() == 4 { 2 } else { f.len() }).sum::<usize>();
+    assert_eq!(stl.len(), 80 + 4 + 50 * triangles);
+}
+
+#[test]
+fn writes_every_format() {
+    let mesh = Mesh::from_world(&world(&[(0, 0, 0)]), false);
+
+    let mut obj = Vec::new();
+    mesh.write(Format::Obj, &mut obj).unwrap();
+    let obj = String::from_utf8(obj).unwrap();
+    assert_eq!(obj.lines().filter(|l| l.starts_with("v ")).count(), 8);
+    assert_eq!(obj.lines().filter(|l| l.starts_with("f ")).count(), 6);
+
+    let mut stl = Vec::new();
+    mesh.write(Format::Stl, &mut stl).unwrap();
+    assert_eq!(stl.len(), 80 + 4 + 12 * 50);
+    assert_eq!(&stl[80..84], &[12, 0, 0, 0]);
+
+    let mut ply = Vec::new();
+    mesh.write(Format::Ply, &mut ply).unwrap();
+    let ply = String::from_utf8(ply).unwrap();
+    assert!(ply.starts_with("ply\n"));
+    assert!(ply.contains("element vertex 8\n"));
+    assert!(ply.contains("element face 6\n"));
+    assert_eq!(ply.lines().skip_while(|&l| l != "end_header").count(), 1 + 8 + 6);
+
+    assert_eq!(Format::from_path("out/world.STL"), Some(Format::Stl));
+    assert_eq!(Format::from_path("world.rle"), None);
+}
@@ -0,0 +1,66 @@
+extern crate game_of_life;
+
+use game_of_life::{State, DenseState, Rule, Soup, Region, Boundary};
+
+fn assert_same(packed: &State, dense: &DenseState, what: &str) {
+    let a: Vec<_> = packed.live_cells().collect();
+    let b: Vec<_> = dense.live_cells().collect();
+    assert!(a == b, "{}: packed and dense worlds differ", what);
+}
+
+fn compare(dim: (usize, usize, usize), rule: &str, boundaries: [Boundary; 3], generations: usize) {
+    let rule = Rule::parse(rule).unwrap();
+    let mut packed = State::new(dim, rule);
+    Soup::new(11).with_region(Region::Whole).with_lattice_skip(None).with_density(0.3).fill(&mut packed);
+    packed.set_boundaries(boundaries);
+
+    let mut dense = DenseState::new(dim, rule);
+    for p in packed.live_cells() {
+        dense.set(p, true);
+    }
+    dense.set_boundaries(boundaries);
+
+    for g in 0..generations {
+        assert_same(&packed, &dense, &format!("{:?} {} {:?} generation {}", dim, rule, boundaries, g));
+        packed.step_forward();
+        dense.step_forward();
+    }
+}
+
+#[test]
+fn matches_dense_across_word_boundaries() {
+    for &zs in [1, 2, 3, 63, 64, 65, 128, 130].iter() {
+        compare((4, 5, zs), "B5/S45", [Boundary::Periodic; 3], 6);
+        compare((4, 5, zs), "B4/S3..6", [Boundary::Periodic; 3], 6);
+    }
+}
+
+#[test]
+fn matches_dense_for_every_boundary() {
+    let all = [Boundary::Periodic, Boundary::Dead, Boundary::Alive, Boundary::Mirror];
+    for &bx in all.iter() {
+        for &bz in all.iter() {
+            compare((6, 5, 70), "B4,5/S4..7", [bx, Boundary::Mirror, bz], 5);
+            compare((3, 2, 2), "B1,2/S1,3", [bx, Boundary::Alive, bz], 5);
+        }
+    }
+}
+
+#[test]
+fn matches_dense_on_slabs() {
+    compare((16, 16, 1), "B3/S23", [Boundary::Periodic; 3], 20);
+    compare((16, 1, 70), "B3/S23", [Boundary::Dead; 3], 20);
+    compare((1, 16, 16), "B3/S23", [Boundary::Mirror; 3], 20);
+}
+
+#[test]
+fn population_and_cell_order_agree() {
+    let mut state = State::new((5, 3, 70), Rule::default());
+    Soup::new(5).with_region(Region::Whole).fill(&mut state);
+    let from_states = state.cell_states().filter(|&c| c).count();
+    assert_eq!(state.population(), from_states);
+    assert_eq!(state.population(), state.live_cells().count());
+    for (i, alive) in state.cell_states().enumerate() {
+        assert_eq!(alive, state.is_alive(game_of_life::i2p(state.dim(), i)));
+    }
+}
@@ -0,0 +1,53 @@
+extern crate game_of_life;
+
+use std::collections::BTreeSet;
+
+use game_of_life::{State, Boundary, World, I3d};
+use game_of_life::patterns::{self, PATTERNS};
+
+fn cells(state: &State) -> BTreeSet<I3d> {
+    World::live_cells(state).collect()
+}
+
+#[test]
+fn patterns_behave_as_described() {
+    for pattern in PATTERNS.iter() {
+        let size = 20;
+        let zs = if pattern.size().2 == 1 && pattern.rule == "B3/S23" { 1 } else { size };
+        let mut state = State::new((size, size, zs), pattern.rule());
+        state.set_boundaries([Boundary::Dead; 3]);
+        let at = (8, 8, if zs == 1 { 0 } else { 8 });
+        pattern.stamp(&mut state, at).unwrap();
+        let start = cells(&state);
+        assert_eq!(start.len(), pattern.cells.len(), "{} has duplicate cells", pattern.name);
+
+        for g in 1..pattern.period {
+            state.step_forward();
+            assert!(cells(&state) != start, "{} repeats after {} generations", pattern.name, g);
+        }
+        state.step_forward();
+        let (dx, dy, dz) = pattern.displacement;
+        let moved: BTreeSet<I3d> = start.iter()
+            .map(|&(x, y, z)| (x + dx as i64, y + dy as i64, z + dz as i64))
+            .collect();
+        assert!(cells(&state) == moved, "{} does not repeat after {} generations", pattern.name, pattern.period);
+    }
+}
+
+#[test]
+fn names_are_unique() {
+    let names: BTreeSet<_> = PATTERNS.iter().map(|p| p.name).collect();
+    assert_eq!(names.len(), PATTERNS.len());
+    assert_eq!(patterns::find("glider-4555").unwrap().rule, "B5/S45");
+    assert!(patterns::find("no-such-pattern").is_none());
+}
+
+#[test]
+fn stamps_only_if_it_fits() {
+    let glider = patterns::find("glider-4555").unwrap();
+    assert_eq!(glider.size(), (3, 4, 2));
+    let mut state = State::new((10, 10, 10), glider.rule());
+    assert!(glider.stamp(&mut state, (7, 6, 8)).is_ok());
+    assert!(glider.stamp(&mut state, (8, 0, 0)).is_err());
+    assert_eq!(state.population(), 10);
+}
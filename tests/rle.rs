@@ -0,0 +1,111 @@
+extern crate game_of_life;
+
+use std::io::Cursor;
+
+use game_of_life::{State, Rule, Soup, Region, SparseWorld, World};
+use game_of_life::rle;
+
+fn read(s: &str) -> std::io::Result<rle::Rle> {
+    rle::read(Cursor::new(s))
+}
+
+#[test]
+fn reads_golly_files() {
+    let pattern = read("3D version=1 size=30 pos=13,13,14 gen=7\n\
+                        #C Bays' glider\n\
+                        x=3 y=4 z=2 rule=3D4,5/5\n\
+                        bo$bo$2o\n$obo/2b\n\
+                        o!\n").unwrap();
+    assert_eq!(pattern.comments, vec!["Bays' glider".to_string()]);
+    assert_eq!(pattern.rule, Some(Rule::parse("B5/S45").unwrap()));
+    assert_eq!(pattern.generation, 7);
+    assert_eq!(pattern.position, Some((13, 13, 14)));
+    assert_eq!(pattern.size, (3, 4, 2));
+    assert_eq!(pattern.cells, vec![(1, 0, 0), (1, 1, 0), (0, 2, 0), (1, 2, 0), (0, 3, 0), (2, 3, 0), (2, 0, 1)]);
+}
+
+#[test]
+fn accepts_spaced_headers_without_3d_line() {
+    let pattern = read("x = 2, y = 1, z = 1, rule = 3D5,6,7/6\n2o!").unwrap();
+    assert_eq!(pattern.rule, Some(Rule::parse("B6/S567").unwrap()));
+    assert_eq!(pattern.position, None);
+    assert_eq!(pattern.cells, vec![(0, 0, 0), (1, 0, 0)]);
+}
+
+#[test]
+fn reports_malformed_input() {
+    let error = |s: &str| read(s).unwrap_err().to_string();
+    assert_eq!(error("3o!"), "line 1: expected an x = .. y = .. z = .. header");
+    assert_eq!(error("x=2 y=1\n2o!"), "line 1: header needs x, y and z");
+    assert_eq!(error("x=2 y=1 z=1\n3o!"), "line 2: cells beyond the 2x1x1 bounding box");
+    assert_eq!(error("x=2 y=1 z=1\n\n2q!"), "line 3: unexpected 'q'");
+    assert_eq!(error("x=a y=1 z=1\n!"), "line 1: invalid x: \"a\"");
+    assert!(error("x=1 y=1 z=1 rule=3D4,5/5F\no!").starts_with("line 1: 3D4,5/5F: invalid rule"));
+    assert_eq!(error("3D version=2\nx=1 y=1 z=1\no!"), "line 1: unsupported version 2");
+    assert_eq!(error(""), "missing x = .. y = .. z = .. header");
+    assert_eq!(error("x=1 y=1 z=1\n99999999999999999999999o!"), "line 2: run count is too large");
+    assert_eq!(error("x=1 y=1 z=1\n18446744073709551615b o!"), "line 2: run reaches beyond any pattern");
+    assert_eq!(error("x=1 y=1 z=1\no$\n18446744073709551615$o!"), "line 3: run reaches beyond any pattern");
+    assert_eq!(error("x=1 y=1 z=1\n/18446744073709551615/o!"), "line 2: run reaches beyond any pattern");
+    assert_eq!(error("x=1 y=1 z=1\n18446744073709551614bo!"), "line 2: cells beyond the 1x1x1 bounding box");
+}
+
+#[test]
+fn round_trips_through_a_state() {
+    let mut state = State::new((40, 30, 90), Rule::parse("B6/S567").unwrap());
+    Soup::new(9).with_region(Region::Box(0.5)).fill(&mut state);
+    state.set_generation(12);
+    let mut file = Vec::new();
+    rle::write(&state, &["a soup".to_string()], &mut file).unwrap();
+    let text = String::from_utf8(file.clone()).unwrap();
+    assert!(text.starts_with("3D version=1 size=90 pos="));
+    assert!(text.lines().all(|l| l.len() <= 70));
+
+    let mut copy = State::new((40, 30, 90), Rule::default());
+    let pattern = rle::load(&mut copy, Cursor::new(file)).unwrap();
+    assert_eq!(pattern.comments, vec!["a soup".to_string()]);
+    assert_eq!(copy.rule(), state.rule());
+    assert_eq!(copy.generation(), 12);
+    assert!(copy.words() == state.words());
+}
+
+#[test]
+fn unbounded_worlds_fill_their_own_grid() {
+    let mut world = SparseWorld::new(Rule::parse("B5/S45").unwrap());
+    for &p in [(-7, 2, -1), (-5, 3, -1), (-6, 2, 4)].iter() {
+        world.set(p, true);
+    }
+    let mut file = Vec::new();
+    rle::write_world(&world, None, &[], &mut file).unwrap();
+    let text = String::from_utf8(file).unwrap();
+    assert!(text.starts_with("3D version=1 size=6 pos=0,0,0\n"), "{}", text);
+    let pattern = read(&text).unwrap();
+    assert_eq!(pattern.position, Some((0, 0, 0)));
+    assert_eq!(pattern.size, (3, 2, 6));
+    let mut cells = pattern.cells.clone();
+    cells.sort();
+    assert_eq!(cells, vec![(0, 0, 0), (1, 0, 5), (2, 1, 0)]);
+
+    let mut state = State::new((6, 6, 6), Rule::default());
+    rle::load(&mut state, Cursor::new(text)).unwrap();
+    assert_eq!(state.population(), 3);
+}
+
+#[test]
+fn empty_worlds_round_trip() {
+    let state = State::new((4, 4, 4), Rule::default());
+    let mut file = Vec::new();
+    rle::write(&state, &[], &mut file).unwrap();
+    let pattern = read(&String::from_utf8(file).unwrap()).unwrap();
+    assert_eq!((pattern.size, pattern.cells.len()), ((0, 0, 0), 0));
+}
+
+#[test]
+fn patterns_without_position_are_centred() {
+    let mut state = State::new((5, 5, 5), Rule::default());
+    rle::load(&mut state, Cursor::new("x=1 y=1 z=1\no!")).unwrap();
+    assert!(state.is_alive((2, 2, 2)));
+    let too_big = rle::load(&mut state, Cursor::new("3D pos=4,4,4\nx=2 y=1 z=1\n2o!"));
+    assert!(too_big.is_err());
+    assert_eq!(state.population(), 1);
+}
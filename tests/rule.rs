@@ -0,0 +1,75 @@
+extern crate game_of_life;
+
+use game_of_life::{Rule, RuleParseError, RuleParseErrorKind};
+use game_of_life::RuleParseErrorKind::*;
+
+fn rule(s: &str) -> Rule {
+    Rule::parse(s).unwrap()
+}
+
+fn error(s: &str) -> (RuleParseErrorKind, usize) {
+    let RuleParseError { kind, position } = Rule::parse(s).unwrap_err();
+    (kind, position)
+}
+
+#[test]
+fn reads_birth_stay_notation() {
+    let r = rule("B5/S45");
+    assert_eq!(r.birth_counts(), vec![5]);
+    assert_eq!(r.stay_counts(), vec![4, 5]);
+    assert_eq!(rule("S45/B5"), r);
+    assert_eq!(rule("  b5/s45 "), r);
+    assert_eq!(rule("B/S"), Rule::new(&[], &[]));
+    assert_eq!(rule("S23"), Rule::new(&[], &[2, 3]));
+    assert_eq!(rule("B5-7/S4..6"), Rule::new(&[5, 6, 7], &[4, 5, 6]));
+    assert_eq!(rule("B5,13/S4..12"), Rule::new(&[5, 13], &[4, 5, 6, 7, 8, 9, 10, 11, 12]));
+}
+
+#[test]
+fn counts_are_single_digits_without_commas() {
+    assert_eq!(rule("B10/S4"), Rule::new(&[0, 1], &[4]));
+    assert_eq!(rule("B45/S5..7"), Rule::new(&[4, 5], &[5, 6, 7]));
+    assert_eq!(rule("B4,5/S5..7"), Rule::new(&[4, 5], &[5, 6, 7]));
+    assert_eq!(rule("B10,4/S"), Rule::new(&[4, 10], &[]));
+    assert_eq!(error("B45,5/S"), (CountOutOfRange(45, 26), 1));
+}
+
+#[test]
+fn reads_bays_and_golly_notations() {
+    assert_eq!(rule("4555"), rule("B5/S45"));
+    assert_eq!(rule("5766"), rule("B6/S567"));
+    assert_eq!(rule("3D4,5/5"), rule("B5/S45"));
+    assert_eq!(rule("3d4,5/5m"), rule("B5/S45"));
+    assert_eq!(rule("3D5..7/6M"), rule("B6/S567"));
+    assert_eq!(rule("3D/13"), Rule::new(&[13], &[]));
+}
+
+#[test]
+fn reports_errors_with_their_position() {
+    assert_eq!(error("   "), (Empty, 0));
+    assert_eq!(error("B5/X4"), (UnexpectedChar('X'), 3));
+    assert_eq!(error("B5/S4;"), (UnexpectedChar(';'), 5));
+    assert_eq!(error("455"), (UnexpectedEnd, 3));
+    assert_eq!(error("B5/S4/B6"), (DuplicatePart('B'), 6));
+    assert_eq!(error("B5,27/S4"), (CountOutOfRange(27, 26), 3));
+    assert_eq!(error("B5/S7-5"), (EmptyRange(7, 5), 4));
+    assert_eq!(error("6545"), (EmptyRange(6, 5), 0));
+    assert_eq!(error("3D4/5V"), (UnsupportedNeighbourhood('V'), 5));
+
+    let e = Rule::parse_with_neighbourhood("B9/S23", 8).unwrap_err();
+    assert_eq!((e.kind.clone(), e.position), (CountOutOfRange(9, 8), 1));
+    assert_eq!(e.to_string(), "invalid rule at column 2: neighbour count 9 exceeds 8");
+}
+
+#[test]
+fn display_reads_back() {
+    for s in ["B5/S45", "B/S", "B0/S", "B5,13/S4..12", "B13/S", "3D/20", "B6/S567"].iter() {
+        let r = rule(s);
+        assert_eq!(rule(&r.to_string()), r, "{} printed as {}", s, r);
+        assert_eq!(rule(&r.to_golly()), r, "{} printed as {}", s, r.to_golly());
+    }
+    assert_eq!(rule("S54/B5").to_string(), "B5/S45");
+    assert_eq!(rule("B5,13/S4").to_string(), "B5,13/S4");
+    assert_eq!(rule("3D/13").to_string(), "3D/13");
+    assert_eq!(rule("4555").to_golly(), "3D4,5/5");
+}
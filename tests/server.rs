@@ -0,0 +1,92 @@
+extern crate game_of_life;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::thread;
+
+use game_of_life::{Rule, State};
+use game_of_life::json::Json;
+use game_of_life::server::{Address, Request, Server, MAX_LINE};
+
+#[test]
+fn parses_addresses_and_requests() {
+    assert_eq!("tcp:7000".parse(), Ok(Address::Tcp(7000)));
+    assert_eq!("7000".parse(), Ok(Address::Tcp(7000)));
+    assert_eq!("unix:/tmp/gol.sock".parse(), Ok(Address::Unix(String::from("/tmp/gol.sock"))));
+    assert_eq!("/tmp/gol.sock".parse(), Ok(Address::Unix(String::from("/tmp/gol.sock"))));
+    assert!("tcp:lots".parse::<Address>().is_err());
+
+    let parse = |s: &str| Request::parse(&Json::parse(s).unwrap());
+    assert_eq!(parse(r#"{"command":"step"}"#), Ok(Request::Step(1)));
+    assert_eq!(parse(r#"{"command":"set","cells":[[1,2,3]]}"#), Ok(Request::Set(vec![(1, 2, 3)], true)));
+    assert_eq!(parse(r#"{"command":"rule","rule":"B5/S45"}"#), Ok(Request::Rule(Some(Rule::parse("B5/S45").unwrap()))));
+    for bad in [r#"{}"#, r#"{"command":"fly"}"#, r#"{"command":"step","generations":-1}"#,
+                r#"{"command":"set","cells":[[1,2]]}"#, r#"{"command":"rule","rule":5}"#].iter() {
+        assert!(parse(bad).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn answers_requests_over_a_socket() {
+    let path = env::temp_dir().join("game-of-life-test.sock").to_str().unwrap().to_string();
+    let mut server = Server::start(&Address::Unix(path.clone())).unwrap();
+    let client = thread::spawn(move || {
+        let stream = UnixStream::connect(&path).unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        let mut ask = |request: &str| {
+            writeln!(writer, "{}", request).unwrap();
+            lines.next().unwrap().unwrap()
+        };
+        vec![
+            ask(r#"{"id": 1, "command": "set", "cells": [[1, 1, 1], [1, 2, 1]]}"#),
+            ask(r#"{"command": "set", "cells": [[9, 9, 9]]}"#),
+            ask(r#"{"command": "cells"}"#),
+            ask(r#"{"command": "rule", "rule": "B3/S23"}"#),
+            ask(r#"{"command": "step", "generations": 2}"#),
+            ask(r#"{"command": "stats"}"#),
+            ask(r#"not json"#),
+            ask(&"[".repeat(MAX_LINE)),
+        ]
+    });
+
+    let mut state = State::new((5, 5, 5), Rule::parse("B5/S45").unwrap());
+    let mut answered = 0;
+    while answered < 6 {
+        if let Some(call) = server.poll() {
+            let result = server.handle(&mut state, &call.request);
+            call.answer(result);
+            answered += 1;
+        } else {
+            thread::yield_now();
+        }
+    }
+    let replies = client.join().unwrap();
+    assert_eq!(replies[0], r#"{"id":1,"ok":true,"population":2}"#);
+    assert_eq!(replies[1], r#"{"ok":false,"error":"cell 9,9,9 lies outside the world"}"#);
+    assert_eq!(replies[2], r#"{"ok":true,"cells":[[1,1,1],[1,2,1]]}"#);
+    assert_eq!(replies[3], r#"{"ok":true,"rule":"B3/S23"}"#);
+    assert_eq!(replies[4], r#"{"ok":true,"generation":2,"population":0}"#);
+    assert!(replies[5].starts_with(r#"{"ok":true,"generation":2,"population":0,"births":0,"deaths":0,"bounding_box":null,"#), "{}", replies[5]);
+    assert!(replies[6].starts_with(r#"{"ok":false,"error":"invalid JSON"#));
+    assert_eq!(replies[7], format!(r#"{{"ok":false,"error":"request is longer than {} bytes"}}"#, MAX_LINE));
+    assert_eq!(state.generation(), 2);
+}
+
+#[test]
+fn replaces_only_stale_sockets() {
+    let path = env::temp_dir().join("game-of-life-test-notes.txt");
+    File::create(&path).unwrap().write_all(b"notes").unwrap();
+    let error = Server::start(&Address::Unix(path.to_str().unwrap().to_string())).err().unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::AddrInUse);
+    let mut notes = String::new();
+    File::open(&path).unwrap().read_to_string(&mut notes).unwrap();
+    assert_eq!(notes, "notes");
+
+    let path = env::temp_dir().join("game-of-life-test-stale.sock");
+    drop(UnixListener::bind(&path));
+    assert!(path.exists());
+    assert!(Server::start(&Address::Unix(path.to_str().unwrap().to_string())).is_ok());
+}
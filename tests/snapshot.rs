@@ -0,0 +1,73 @@
+extern crate game_of_life;
+
+use std::io::Cursor;
+
+use game_of_life::{State, Rule, Soup, Boundary};
+
+fn world() -> State {
+    let mut state = State::new((13, 7, 100), Rule::parse("B5,6/S4..7").unwrap());
+    Soup::new(21).fill(&mut state);
+    state.set_boundaries([Boundary::Mirror, Boundary::Alive, Boundary::Dead]);
+    for _ in 0..3 {
+        state.step_forward();
+    }
+    state
+}
+
+fn save(state: &State) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    state.save_snapshot(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn round_trips_everything() {
+    let state = world();
+    let copy = State::load_snapshot(Cursor::new(save(&state))).unwrap();
+    assert_eq!(copy.dim(), state.dim());
+    assert_eq!(copy.rule(), state.rule());
+    assert_eq!(copy.boundaries(), state.boundaries());
+    assert_eq!(copy.generation(), 3);
+    assert_eq!(copy.seed(), Some(21));
+    assert!(copy.words() == state.words());
+
+    let mut state = state;
+    let mut copy = copy;
+    state.step_forward();
+    copy.step_forward();
+    assert!(copy.words() == state.words());
+}
+
+#[test]
+fn keeps_missing_seeds_missing() {
+    let state = State::new((2, 3, 4), Rule::default());
+    let copy = State::load_snapshot(Cursor::new(save(&state))).unwrap();
+    assert_eq!(copy.seed(), None);
+}
+
+#[test]
+fn rejects_damaged_files() {
+    let error = |bytes: Vec<u8>| match State::load_snapshot(Cursor::new(bytes)) {
+        Ok(_) => panic!("damaged snapshot loaded"),
+        Err(e) => e.to_string(),
+    };
+    let bytes = save(&world());
+
+    assert_eq!(error(b"P3\n1 1\n".to_vec()), "not a snapshot");
+
+    let mut newer = bytes.clone();
+    newer[4] = 9;
+    assert_eq!(error(newer), "unsupported snapshot version 9");
+
+    let mut boundary = bytes.clone();
+    boundary[26] = 7;
+    assert_eq!(error(boundary), "invalid boundary 7");
+
+    let mut flipped = bytes.clone();
+    flipped[60] ^= 1;
+    assert_eq!(error(flipped), "snapshot is corrupt: checksum mismatch");
+
+    let mut short = bytes.clone();
+    short.truncate(100);
+    assert!(State::load_snapshot(Cursor::new(short)).is_err());
+}
@@ -0,0 +1,36 @@
+extern crate game_of_life;
+
+use game_of_life::{Soup, Region, State, Rule, U3d};
+
+fn cells(soup: Soup, dim: U3d) -> Vec<U3d> {
+    let mut state = State::new(dim, Rule::default());
+    soup.fill(&mut state);
+    assert_eq!(state.seed(), Some(soup.seed()));
+    state.live_cells().collect()
+}
+
+#[test]
+fn same_recipe_gives_same_cells() {
+    let dim = (20, 16, 24);
+    for &region in [Region::Whole, Region::Box(0.5), Region::Sphere(0.8)].iter() {
+        let soup = Soup::new(42).with_region(region).with_density(0.3);
+        let first = cells(soup, dim);
+        assert!(!first.is_empty());
+        assert_eq!(cells(soup, dim), first);
+        assert_eq!(cells(Soup::new(42).with_region(region).with_density(0.3), dim), first);
+        assert!(cells(Soup::new(43).with_region(region).with_density(0.3), dim) != first);
+        assert!(cells(soup.next_seed(), dim) != first);
+    }
+    assert_eq!(Soup::new(42).next_seed().seed(), 43);
+    assert!(cells(Soup::new(42).with_region(Region::Box(0.5)), dim) != cells(Soup::new(42), dim));
+}
+
+#[test]
+fn cells_stay_inside_the_region() {
+    let dim = (20, 20, 20);
+    let boxed = cells(Soup::new(1).with_region(Region::Box(0.5)).with_density(1.0).with_lattice_skip(None), dim);
+    assert!(boxed.iter().all(|&(x, y, z)| [x, y, z].iter().all(|&c| 5 <= c && c <= 15)));
+    let skipped = cells(Soup::new(1).with_region(Region::Whole).with_density(1.0).with_lattice_skip(Some(5)), dim);
+    assert!(skipped.iter().all(|&(x, y, z)| x % 5 != 0 && y % 5 != 0 && z % 5 != 0));
+    assert_eq!(skipped.len(), 16 * 16 * 16);
+}
@@ -0,0 +1,84 @@
+extern crate game_of_life;
+
+use game_of_life::{State, SparseWorld, World, Rule, Soup, Region, Boundary};
+use game_of_life::console::{Command, Console};
+
+/// A bounded world big enough that nothing reaches its dead edges within
+/// `generations`, and the same cells in an unbounded world shifted by
+/// `offset`.
+fn pair(rule: &str, generations: usize, offset: i64) -> (State, SparseWorld) {
+    let size = 12 + 2 * generations + 2;
+    let rule = Rule::parse(rule).unwrap();
+    let mut state = State::new((size, size, size), rule);
+    Soup::new(9).with_region(Region::Box(12.0 / size as f64)).with_lattice_skip(None).fill(&mut state);
+    state.set_boundaries([Boundary::Dead; 3]);
+
+    let mut sparse = SparseWorld::new(rule);
+    for (x, y, z) in World::live_cells(&state) {
+        sparse.set((x + offset, y + offset, z + offset), true);
+    }
+    (state, sparse)
+}
+
+fn run(rule: &str, generations: usize, offset: i64) {
+    let (mut state, mut sparse) = pair(rule, generations, offset);
+    for g in 0..generations + 1 {
+        let mut expected: Vec<_> = World::live_cells(&state)
+            .map(|(x, y, z)| (x + offset, y + offset, z + offset))
+            .collect();
+        let mut actual: Vec<_> = sparse.live_cells().collect();
+        expected.sort();
+        actual.sort();
+        assert!(expected == actual, "{} offset {} generation {}", rule, offset, g);
+        state.step_forward();
+        sparse.step();
+    }
+}
+
+#[test]
+fn matches_bounded_world_across_chunk_edges() {
+    for &offset in [0, -20, -70, 57].iter() {
+        run("B5/S45", 8, offset);
+        run("B4/S3..6", 6, offset);
+    }
+}
+
+#[test]
+fn set_and_clear_free_chunks() {
+    let mut world = SparseWorld::new(Rule::default());
+    world.set((-1, -100, 1000), true);
+    assert!(world.is_alive((-1, -100, 1000)));
+    assert_eq!(world.chunk_count(), 1);
+    world.set((-1, -100, 1000), false);
+    assert_eq!(world.chunk_count(), 0);
+    assert_eq!(world.population(), 0);
+}
+
+#[test]
+fn dying_patterns_release_chunks() {
+    let mut world = SparseWorld::new(Rule::default());
+    world.set((5, 5, 5), true);
+    world.step();
+    assert_eq!(world.population(), 0);
+    assert_eq!(world.chunk_count(), 0);
+    world.step();
+    assert!(world.is_static());
+}
+
+#[test]
+#[should_panic]
+fn rejects_b0_rules() {
+    SparseWorld::new(Rule::new(&[0], &[]));
+}
+
+#[test]
+fn console_refuses_b0_rules() {
+    let mut world = SparseWorld::new(Rule::default());
+    let mut console = Console::new();
+    let b0 = Rule::parse("B0/S").unwrap();
+    assert_eq!(world.check_rule(&b0),
+               Err(String::from("B0/S gives birth with no neighbours, which an unbounded world cannot run")));
+    assert!(console.execute(&mut world, &Command::Rule(b0)).is_err());
+    assert_eq!(*world.rule(), Rule::default());
+    assert!(State::new((4, 4, 4), Rule::default()).check_rule(&b0).is_ok());
+}
@@ -0,0 +1,84 @@
+extern crate game_of_life;
+
+use game_of_life::{State, Rule, Boundary, SparseWorld, GenerationStats};
+use game_of_life::batch::{self, BatchConfig, OnSettle};
+use game_of_life::stats::{Tracker, StatsWriter, Format};
+
+fn blinker() -> State {
+    let mut state = State::new((10, 10, 1), Rule::parse("B3/S23").unwrap());
+    state.set_boundaries([Boundary::Dead; 3]);
+    for y in 3..6 {
+        state.set((4, y, 0), true);
+    }
+    state
+}
+
+#[test]
+fn births_and_deaths_follow_the_pattern() {
+    let mut state = blinker();
+    let mut tracker = Tracker::new();
+    let first = tracker.record(&state);
+    assert_eq!(first, GenerationStats {
+        generation: 0,
+        population: 3,
+        births: 0,
+        deaths: 0,
+        bounding_box: Some(((4, 3, 0), (4, 5, 0))),
+        centre_of_mass: Some((4.0, 4.0, 0.0)),
+    });
+    state.step_forward();
+    let second = tracker.record(&state);
+    assert_eq!((second.generation, second.births, second.deaths), (1, 2, 2));
+    assert_eq!(second.bounding_box, Some(((3, 4, 0), (5, 4, 0))));
+}
+
+#[test]
+fn empty_worlds_have_no_box() {
+    let world = SparseWorld::new(Rule::default());
+    let stats = Tracker::new().record(&world);
+    assert_eq!((stats.population, stats.bounding_box, stats.centre_of_mass), (0, None, None));
+}
+
+#[test]
+fn csv_and_json_lines() {
+    let mut state = blinker();
+    let mut tracker = Tracker::new();
+    let mut rows = vec![tracker.record(&state)];
+    state.clear();
+    state.step_forward();
+    rows.push(tracker.record(&state));
+
+    let mut csv = StatsWriter::new(Vec::new(), Format::Csv);
+    let mut json = StatsWriter::new(Vec::new(), Format::JsonLines);
+    for row in rows.iter() {
+        csv.write(row).unwrap();
+        json.write(row).unwrap();
+    }
+    let csv = String::from_utf8(csv.into_inner()).unwrap();
+    let json = String::from_utf8(json.into_inner()).unwrap();
+    assert_eq!(csv, "generation,population,births,deaths,min_x,min_y,min_z,max_x,max_y,max_z,centre_x,centre_y,centre_z\n\
+                     0,3,0,0,4,3,0,4,5,0,4,4,0\n\
+                     1,0,0,3,,,,,,,,,\n");
+    assert_eq!(json, "{\"generation\":0,\"population\":3,\"births\":0,\"deaths\":0,\
+                      \"bounding_box\":[[4,3,0],[4,5,0]],\"centre_of_mass\":[4,4,0]}\n\
+                      {\"generation\":1,\"population\":0,\"births\":0,\"deaths\":3,\
+                      \"bounding_box\":null,\"centre_of_mass\":null}\n");
+}
+
+#[test]
+fn format_from_name() {
+    assert_eq!(Format::from_path("run.jsonl"), Format::JsonLines);
+    assert_eq!(Format::from_path("run.csv"), Format::Csv);
+    assert_eq!("json".parse::<Format>(), Ok(Format::JsonLines));
+    assert!("xml".parse::<Format>().is_err());
+}
+
+#[test]
+fn batch_runs_record_every_generation() {
+    let mut state = blinker();
+    let config = BatchConfig { generations: 6, on_settle: OnSettle::Continue, max_period: 8, record_stats: true };
+    let result = batch::run(&mut state, &config, |_| ());
+    assert_eq!(result.stats.len(), 7);
+    assert!(result.stats.iter().skip(1).all(|s| s.births == 2 && s.deaths == 2));
+    assert_eq!(result.stats[6].generation, 6);
+}
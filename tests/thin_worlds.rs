@@ -0,0 +1,88 @@
+extern crate game_of_life;
+
+use game_of_life::{State, Rule, Soup, Region, Boundary};
+
+fn life_2d() -> Rule {
+    Rule::parse_with_neighbourhood("B3/S23", 8).unwrap()
+}
+
+fn live(state: &State) -> Vec<(usize, usize, usize)> {
+    state.live_cells().collect()
+}
+
+#[test]
+fn slab_has_2d_neighbourhood() {
+    assert_eq!(State::new((50, 50, 1), Rule::default()).neighbourhood_size(), 8);
+    assert_eq!(State::new((50, 1, 50), Rule::default()).neighbourhood_size(), 8);
+    assert_eq!(State::new((50, 1, 1), Rule::default()).neighbourhood_size(), 2);
+    assert_eq!(State::new((2, 2, 2), Rule::default()).neighbourhood_size(), 26);
+}
+
+#[test]
+fn rule_counts_are_checked_against_the_neighbourhood() {
+    assert!(Rule::parse_with_neighbourhood("B3/S23", 8).is_ok());
+    assert!(Rule::parse_with_neighbourhood("B9/S23", 8).is_err());
+
+    let slab = State::new((50, 50, 1), life_2d());
+    assert!(slab.check_rule(&Rule::parse("B3/S238").unwrap()).is_ok());
+    assert_eq!(slab.check_rule(&Rule::parse("B9/S23").unwrap()),
+               Err(String::from("B9/S23 counts up to 9 neighbours, but cells of this world have 8")));
+    assert!(State::new((5, 5, 5), life_2d()).check_rule(&Rule::parse("B9/S23").unwrap()).is_ok());
+}
+
+#[test]
+fn blinker_in_slab() {
+    let mut state = State::new((5, 5, 1), life_2d());
+    for y in 1..4 {
+        state.set((2, y, 0), true);
+    }
+    let vertical = live(&state);
+    state.step_forward();
+    assert_eq!(live(&state), vec![(1, 2, 0), (2, 2, 0), (3, 2, 0)]);
+    state.step_forward();
+    assert_eq!(live(&state), vertical);
+}
+
+#[test]
+fn glider_in_slab_crosses_torus() {
+    let mut state = State::new((8, 8, 1), life_2d());
+    for &(x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        state.set((x, y, 0), true);
+    }
+    let start = live(&state);
+    for _ in 0..4 * 8 {
+        state.step_forward();
+        assert_eq!(state.population(), 5);
+    }
+    assert_eq!(live(&state), start);
+}
+
+#[test]
+fn size_two_axes_wrap_to_the_same_cell_twice() {
+    // On a periodic 2x2x2 world a lone cell is seen once along an axis it
+    // shares with the observer and twice along one it does not.
+    let mut state = State::new((2, 2, 2), Rule::new(&[8], &[]));
+    state.set((0, 0, 0), true);
+    state.step_forward();
+    assert_eq!(live(&state), vec![(1, 1, 1)]);
+}
+
+#[test]
+fn every_thin_shape_and_boundary_steps() {
+    let boundaries = [Boundary::Periodic, Boundary::Dead, Boundary::Alive, Boundary::Mirror];
+    for xs in 1..4 {
+        for ys in 1..4 {
+            for zs in 1..4 {
+                for &b in boundaries.iter() {
+                    let mut state = State::new((xs, ys, zs), Rule::new(&[1, 2, 3], &[1, 2]));
+                    Soup::new(7).with_region(Region::Whole).with_lattice_skip(None).fill(&mut state);
+                    state.set_boundaries([b; 3]);
+                    for _ in 0..3 {
+                        state.step_forward();
+                    }
+                    assert!(state.live_cells().all(|(x, y, z)| x < xs && y < ys && z < zs));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+extern crate game_of_life;
+
+use game_of_life::{State, Rule, Soup, Boundary};
+
+fn world(dim: (usize, usize, usize), threads: usize, boundaries: [Boundary; 3]) -> State {
+    let mut state = State::new(dim, Rule::parse("B4,5/S4..7").unwrap());
+    Soup::new(3).fill(&mut state);
+    state.set_boundaries(boundaries);
+    state.set_threads(threads);
+    state
+}
+
+#[test]
+fn threaded_steps_are_bit_identical() {
+    // The small world is stepped on one thread whatever is asked for.
+    for &dim in [(15, 16, 70), (80, 40, 70)].iter() {
+        for &b in [Boundary::Periodic, Boundary::Alive, Boundary::Mirror].iter() {
+            let mut serial = world(dim, 1, [b; 3]);
+            let mut threaded: Vec<_> = [2, 3, 8, 16, 64].iter().map(|&t| world(dim, t, [b; 3])).collect();
+            for _ in 0..8 {
+                serial.step_forward();
+                for state in threaded.iter_mut() {
+                    state.step_forward();
+                    assert!(state.words() == serial.words(), "{} threads diverged", state.threads());
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn zero_threads_means_one_per_cpu() {
+    let mut state = State::new((4, 4, 4), Rule::default());
+    state.set_threads(0);
+    assert!(state.threads() >= 1);
+}
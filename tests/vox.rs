@@ -0,0 +1,90 @@
+extern crate game_of_life;
+
+use std::io::Cursor;
+
+use game_of_life::{State, Rule, Soup, SparseWorld, World};
+use game_of_life::vox;
+
+fn rule() -> Rule {
+    Rule::parse("B5/S45").unwrap()
+}
+
+/// A file as MagicaVoxel writes it, with a PACK chunk and a palette around
+/// the model.
+fn magica_file() -> Vec<u8> {
+    let mut children = Vec::new();
+    children.extend(b"PACK\x04\0\0\0\0\0\0\0\x01\0\0\0");
+    children.extend(b"SIZE\x0c\0\0\0\0\0\0\0\x03\0\0\0\x02\0\0\0\x04\0\0\0");
+    children.extend(b"XYZI\x10\0\0\0\0\0\0\0\x03\0\0\0");
+    children.extend(&[0, 0, 0, 1, 2, 1, 3, 7, 1, 1, 1, 0]);
+    children.extend(b"RGBA\x00\x04\0\0\0\0\0\0");
+    children.extend(vec![255; 1024]);
+    let mut file = Vec::new();
+    file.extend(b"VOX \x96\0\0\0MAIN\0\0\0\0");
+    file.extend(&[children.len() as u8, (children.len() >> 8) as u8, 0, 0]);
+    file.extend(children);
+    file
+}
+
+#[test]
+fn reads_magica_voxel_files() {
+    let model = vox::read(Cursor::new(magica_file())).unwrap();
+    assert_eq!(model.size, (3, 2, 4));
+    // The voxel with palette index 0 is empty.
+    assert_eq!(model.voxels, vec![((0, 0, 0), 1), ((2, 1, 3), 7)]);
+    assert_eq!(model.cells(Some(&[7])), vec![(2, 1, 3)]);
+    assert_eq!(model.cells(None).len(), 2);
+}
+
+#[test]
+fn round_trips_a_world() {
+    let mut state = State::new((20, 30, 40), rule());
+    Soup::new(5).fill(&mut state);
+    let mut bytes = Vec::new();
+    vox::write(&state, 3, &mut bytes).unwrap();
+
+    let model = vox::read(Cursor::new(&bytes[..])).unwrap();
+    assert_eq!(model.size, (20, 30, 40));
+    assert!(model.voxels.iter().all(|&(_, c)| c == 3));
+
+    let mut copy = State::new((20, 30, 40), rule());
+    vox::load(&mut copy, Cursor::new(bytes), None).unwrap();
+    assert_eq!(copy.words(), state.words());
+}
+
+#[test]
+fn loads_centred_and_filtered_by_colour() {
+    let mut state = State::new((7, 6, 8), rule());
+    vox::load(&mut state, Cursor::new(magica_file()), Some(&[1])).unwrap();
+    assert_eq!(state.live_cells().collect::<Vec<_>>(), vec![(2, 2, 2)]);
+
+    let mut small = State::new((2, 2, 2), rule());
+    assert!(vox::load(&mut small, Cursor::new(magica_file()), None).is_err());
+    assert_eq!(small.population(), 0);
+}
+
+#[test]
+fn writes_the_bounding_box_of_unbounded_worlds() {
+    let mut world = SparseWorld::new(rule());
+    world.set((-5, 10, 1000), true);
+    world.set((-3, 10, 1001), true);
+    let mut bytes = Vec::new();
+    vox::write_world(&world, vox::DEFAULT_COLOUR, &mut bytes).unwrap();
+    let model = vox::read(Cursor::new(bytes)).unwrap();
+    assert_eq!(model.size, (3, 1, 2));
+    assert_eq!(model.cells(None), vec![(0, 0, 0), (2, 0, 1)]);
+
+    world.set((300, 10, 1000), true);
+    assert!(vox::write_world(&world, vox::DEFAULT_COLOUR, &mut Vec::new()).is_err());
+}
+
+#[test]
+fn rejects_broken_files() {
+    assert!(vox::read(Cursor::new(&b"RIFF\x96\0\0\0"[..])).is_err());
+    let file = magica_file();
+    assert!(vox::read(Cursor::new(&file[..60])).is_err());
+    let mut outside = file.clone();
+    // First voxel's x, beyond the 3 wide model.
+    outside[76] = 5;
+    assert!(vox::read(Cursor::new(outside)).is_err());
+}